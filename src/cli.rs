@@ -1,3 +1,9 @@
+pub mod app;
+pub mod commands;
+pub mod completions;
 pub mod handler;
+pub mod helper;
 pub mod input;
-pub mod log;
\ No newline at end of file
+pub mod log;
+pub mod registry;
+pub mod script;
\ No newline at end of file
@@ -0,0 +1,115 @@
+extern crate clap;
+
+use clap::{Arg, ArgAction, ArgMatches, Command, builder::PossibleValue};
+
+use super::commands::COMMANDS;
+use super::completions::{SHELLS, IDS};
+use super::input::Args;
+use super::registry::{find, Param};
+
+/// Turns a command's parameters into arguments for the parser.
+fn arg(param: &Param) -> Arg {
+    match *param {
+        Param::Required(n) => Arg::new(n)
+            .value_name(n)
+            .required(true),
+        Param::Optional(n) | Param::Keyword(n) => Arg::new(n)
+            .value_name(n),
+        Param::Rest(n) => Arg::new(n)
+            .value_name(n)
+            .required(true)
+            .num_args(1..)
+            .trailing_var_arg(true)
+            .allow_hyphen_values(true),
+        Param::More(n) => Arg::new(n)
+            .value_name(n)
+            .num_args(1..)
+            .trailing_var_arg(true)
+            .allow_hyphen_values(true),
+        Param::Flag(n) => Arg::new(n)
+            .long(n)
+            .action(ArgAction::SetTrue)
+    }
+}
+
+/// Builds the argument parser, with a subcommand for every non-interactive command.
+pub fn app() -> Command {
+    let subcommands = COMMANDS.iter()
+        .filter(|c| !c.interactive)
+        .map(|c| Command::new(c.name)
+            .about(c.summary())
+            .long_about(c.help)
+            .visible_aliases(c.aliases)
+            // Without a class, there's one argument fewer, so counts are checked when running
+            .args(c.params.iter().map(move |p| if c.takes_assignment() { arg(p).required(false) } else { arg(p) })));
+
+    Command::new("skid")
+        .about("A class-assignment scheduler")
+        .version(env!("CARGO_PKG_VERSION"))
+        .after_help("Run without a command to start an interactive session.")
+        .arg(Arg::new("file")
+            .short('f')
+            .long("file")
+            .value_name("PATH")
+            .global(true)
+            .action(ArgAction::Set)
+            .help("Uses a data file instead of the active profile [env: SKID_FILE]"))
+        .subcommands(subcommands)
+        .subcommand(Command::new("run")
+            .about("Runs the commands in a file, one per line.")
+            .long_about("Runs the commands in a file, one per line.\nBlank lines and lines starting with '#' are ignored.\n\nStops at the first failing command and writes nothing\nunless '--keep-going' is given.")
+            .arg(Arg::new("path")
+                .value_name("path")
+                .required(true))
+            .arg(Arg::new("keep-going")
+                .short('k')
+                .long("keep-going")
+                .action(ArgAction::SetTrue)
+                .help("Runs the remaining commands after one fails"))
+            .arg(Arg::new("dry-run")
+                .short('n')
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Reports what would change without writing")))
+        .subcommand(Command::new("completions")
+            .about("Prints a shell completion script or the man page.")
+            .long_about("Prints a shell completion script or the man page.\n\nLoad the script in your shell's startup file, for example:\n  source <(skid completions bash)\n\nClass IDs are completed from the current data file.")
+            .arg(Arg::new("shell")
+                .value_name("shell")
+                .required(true)
+                .value_parser(SHELLS.iter()
+                    .map(|s| PossibleValue::new(*s))
+                    .chain(std::iter::once(PossibleValue::new(IDS).hide(true)))
+                    .collect::<Vec<PossibleValue>>())))
+}
+
+/// Converts a parsed subcommand into the arguments a REPL command would get.
+pub fn args_from(matches: &ArgMatches) -> Option<Args> {
+    let (name, sub) = matches.subcommand()?;
+    let command = find(name)?;
+
+    let mut list = Vec::new();
+    let mut flags = Vec::new();
+
+    for param in command.params {
+        match *param {
+            Param::Flag(n) => if sub.get_flag(n) {
+                flags.push(String::from(n));
+            },
+            Param::Required(n) | Param::Optional(n) | Param::Keyword(n) | Param::Rest(n) | Param::More(n) => {
+                if let Some(values) = sub.get_many::<String>(n) {
+                    list.extend(values.cloned());
+                }
+            }
+        }
+    }
+
+    // Flags are passed on as they'd be typed, for the command to validate
+    list.extend(flags.into_iter().map(|f| format!("--{}", f)));
+
+    Some(Args {
+        command: String::from(name),
+        list,
+        flags: Vec::new()
+    })
+}
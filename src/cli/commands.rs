@@ -0,0 +1,865 @@
+extern crate chrono;
+
+use std::path::PathBuf;
+
+use chrono::Local;
+
+use super::{handler::{run_line, late_list, Session}, input::Args, log::*, registry::{find, Command, Param::*}, script::{run_script, dry_run}};
+use super::super::structs::{classes::{Classes, SortingMethod}, class::{Class, List}, assignment::{Assignment, DueTime}};
+use super::super::util::{dates::date_words, config::{query_assignments, write_config, write_settings, read_config, load_config, encode_config, convert_config, config_file, config_format, file_overridden}, backup::list_backups, storage::Format};
+use super::super::util::settings::{settings, style, Role, KEYS};
+use super::super::util::profile::{active_profile, set_active_profile, list_profiles, create_profile, profile_exists, valid_profile_name};
+
+/// Every command, in alphabetical order.
+pub const COMMANDS: [Command; 32] = [
+    Command {
+        name: "add",
+        aliases: &["a"],
+        params: &[Required("class"), Required("date"), Rest("name")],
+        help: "Adds a dated assignment to a class.\n\nDates are formatted as 'd-m-y' by default.\nExample: 31-1-2021\n\nChange the format with 'set input_date_format'.\n\nDates can also be written relative to today:\ntoday, tomorrow, fri, next tue, +3d, 2w, 2026-11-03 or nov 3\n\nA due time can follow the date, like 23:59, 11:59pm or 9am,\nor p3 for the start of period 3 (see 'set period_times').\n\nThe resolved date is shown before the assignment is added.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: add
+    },
+    Command {
+        name: "all",
+        aliases: &[],
+        params: &[],
+        help: "Displays assignments across all classes.",
+        mutates: false,
+        side_effects: false,
+        interactive: false,
+        run: all
+    },
+    Command {
+        name: "archive",
+        aliases: &[],
+        params: &[Keyword("restore"), Optional("class"), Optional("index")],
+        help: "Displays deleted classes and cleaned assignments, or those of one class.\n\nBring a deleted class back with 'archive restore <class>', or a\ncleaned assignment with 'archive restore <class> <index>'.\nA cleaned assignment can also be given by ID, like 'archive restore a3'.\n\nRemove things from the archive for good with 'purge'.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: archive
+    },
+    Command {
+        name: "clean",
+        aliases: &[],
+        params: &[Required("class")],
+        help: "Moves all completed assignments of a class to the archive.\nSee them with 'archive'.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: clean
+    },
+    Command {
+        name: "complete",
+        aliases: &["c"],
+        params: &[Required("class"), Required("index")],
+        help: "Moves an assignment to a class's completed list.\nGive the assignment by index or ID, like 'complete math 2' or 'complete a3'.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: complete
+    },
+    Command {
+        name: "create",
+        aliases: &[],
+        params: &[Required("id"), Required("period"), Rest("name")],
+        help: "Creates a class with metadata.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: create
+    },
+    Command {
+        name: "delete",
+        aliases: &[],
+        params: &[Required("class")],
+        help: "Deletes a class, including all of its assignments.\nThe class is kept in the archive until purged, so it can be restored with 'archive restore'.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: delete
+    },
+    Command {
+        name: "edit",
+        aliases: &["e"],
+        params: &[Required("class"), Required("index"), Required("field"), Rest("value")],
+        help: "Changes the name, date or time of an assignment in place.\nThe class can be left out when giving an ID, like 'edit a3 name Essay'.\n\nDates are written like in 'add'.\nTimes are written like 23:59, 11:59pm or p3, or none to remove the time.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: edit
+    },
+    Command {
+        name: "encode",
+        aliases: &[],
+        params: &[],
+        help: "Displays encoded class data.",
+        mutates: false,
+        side_effects: false,
+        interactive: false,
+        run: encode
+    },
+    Command {
+        name: "format",
+        aliases: &[],
+        params: &[Optional("format")],
+        help: "Displays or changes the storage format of the data file.\nFormats: line (default), json, toml.\n\nChanging the format moves the data to a file with the\nformat's extension and keeps the old file as a backup.\nData files set with '--file' can't be changed.",
+        mutates: false,
+        side_effects: true,
+        interactive: false,
+        run: format
+    },
+    Command {
+        name: "get",
+        aliases: &[],
+        params: &[Optional("setting")],
+        help: "Displays the value of a setting.\nIf no setting is supplied, displays all settings.",
+        mutates: false,
+        side_effects: false,
+        interactive: false,
+        run: get
+    },
+    Command {
+        name: "help",
+        aliases: &["h"],
+        params: &[Optional("command")],
+        help: "Displays help info for a command.\nIf no command is supplied, displays all commands.",
+        mutates: false,
+        side_effects: false,
+        interactive: true,
+        run: help
+    },
+    Command {
+        name: "history",
+        aliases: &[],
+        params: &[Keyword("search|run"), More("query")],
+        help: "Displays the operations performed this session.\nUndone operations are listed last.\n\nSearch the commands typed in earlier sessions with\n'history search (<text...>)', then run one again with\n'history run <number>'.\n\nThe number of commands kept is set with 'set history_size'.",
+        mutates: false,
+        side_effects: false,
+        interactive: true,
+        run: history
+    },
+    Command {
+        name: "info",
+        aliases: &["i"],
+        params: &[Optional("class")],
+        help: "Displays class info and assignments.\nIf no class is supplied, displays all class info.",
+        mutates: false,
+        side_effects: false,
+        interactive: false,
+        run: info
+    },
+    Command {
+        name: "klog",
+        aliases: &[],
+        params: &[Required("avg"), Optional("path")],
+        help: "Displays assignment data in klog format.\nThis is particularly useful for keeping track of\nassignments you've completed with date and time.\n\nThe 'avg' argument is how many hours on average\nyou'd expect to complete the assignments in.\nYou can modify these values after writing.\n\nOptionally specify a path to write to.\n'.klg' is automatically appended to the path.\n\nLearn more about klog at: https://klog.jotaen.net",
+        mutates: false,
+        side_effects: true,
+        interactive: false,
+        run: klog
+    },
+    Command {
+        name: "late",
+        aliases: &[],
+        params: &[],
+        help: "Displays late assignments across all classes.",
+        mutates: false,
+        side_effects: false,
+        interactive: false,
+        run: late
+    },
+    Command {
+        name: "list",
+        aliases: &["ls", "l"],
+        params: &[Optional("sort")],
+        help: "Lists all classes by ID and name.\nYou can sort classes by id, name and period.\nThe default is set with 'set default_sort'.",
+        mutates: false,
+        side_effects: false,
+        interactive: false,
+        run: list
+    },
+    Command {
+        name: "modify",
+        aliases: &["mod", "m"],
+        params: &[Required("class"), Required("property"), Rest("value")],
+        help: "Modifies class metadata by input.\nTo change a class ID, use 'rename'.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: modify
+    },
+    Command {
+        name: "move",
+        aliases: &["mv"],
+        params: &[Required("class"), Required("index"), Required("to-class"), Flag("completed")],
+        help: "Moves an assignment to another class, keeping its date, time and ID.\nGive the assignment by index or ID, like 'move math 2 eng' or 'move a3 eng'.\nWith --completed, moves a completed assignment instead.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: move_assignment
+    },
+    Command {
+        name: "panic",
+        aliases: &[],
+        params: &[],
+        help: "Prevents writing to config upon exiting the program.\nThis is useful if you've made an irreversible mistake while editing.",
+        mutates: false,
+        side_effects: false,
+        interactive: true,
+        run: panic
+    },
+    Command {
+        name: "profile",
+        aliases: &["p"],
+        params: &[Keyword("create"), Optional("name")],
+        help: "Lists profiles, or switches to a profile by name.\nEach profile keeps its own classes and assignments.\nChanges are written before switching.\n\nCreate a profile with 'profile create <name>'.",
+        mutates: false,
+        side_effects: true,
+        interactive: false,
+        run: profile
+    },
+    Command {
+        name: "purge",
+        aliases: &[],
+        params: &[Required("class"), Optional("index"), Flag("all")],
+        help: "Permanently removes things from the archive.\n\nGiven a deleted class, removes it for good.\nGiven a class, removes one of its cleaned assignments\nwith 'purge <class> <index>', or all of them with\n'purge <class> --all'.\nA cleaned assignment can also be given by ID, like 'purge a3'.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: purge
+    },
+    Command {
+        name: "quit",
+        aliases: &["q"],
+        params: &[],
+        help: "Exits the program.",
+        mutates: false,
+        side_effects: false,
+        interactive: true,
+        run: quit
+    },
+    Command {
+        name: "redo",
+        aliases: &[],
+        params: &[],
+        help: "Reapplies the last undone operation.",
+        mutates: false,
+        side_effects: false,
+        interactive: true,
+        run: redo
+    },
+    Command {
+        name: "remove",
+        aliases: &["r"],
+        params: &[Required("class"), Required("index")],
+        help: "Removes an assignment without completing it.\nGive the assignment by index or ID, like 'remove math 2' or 'remove a3'.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: remove
+    },
+    Command {
+        name: "rename",
+        aliases: &[],
+        params: &[Required("class"), Required("id")],
+        help: "Changes the ID of a class, keeping all of its assignments.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: rename
+    },
+    Command {
+        name: "restore",
+        aliases: &[],
+        params: &[Optional("index")],
+        help: "Lists config backups, or restores one by index.\nBackups made before switching formats are listed with their format.\nThe current config is backed up before restoring.",
+        mutates: false,
+        side_effects: true,
+        interactive: false,
+        run: restore
+    },
+    Command {
+        name: "set",
+        aliases: &[],
+        params: &[Required("setting"), Rest("value")],
+        help: "Changes a setting and saves it to the settings file.\nRun 'get' to view all settings.\n\nDate formats use strftime syntax, e.g. '%d-%m-%Y'.\nColors are names (black, red, green, yellow, blue,\npurple, cyan, white) or numbers from 0 to 255.",
+        mutates: false,
+        side_effects: true,
+        interactive: false,
+        run: set
+    },
+    Command {
+        name: "source",
+        aliases: &[],
+        params: &[Rest("path"), Flag("keep-going"), Flag("dry-run")],
+        help: "Runs the commands in a file, one per line.\nBlank lines and lines starting with '#' are ignored.\n\nStops at the first failing command unless '--keep-going'\nis given. With '--dry-run', reports what would change\nwithout changing anything.\n\nFrom the command line, use 'skid run <path>'.",
+        mutates: false,
+        side_effects: false,
+        interactive: true,
+        run: source
+    },
+    Command {
+        name: "uncomplete",
+        aliases: &["uc"],
+        params: &[Required("class"), Required("index"), More("date")],
+        help: "Moves a completed assignment back to the list of assignments.\nGive it by its index in the completed list or its ID, like 'uncomplete a3'.\n\nA new due date can follow, like 'uncomplete a3 fri'. Assignments completed\nbefore due dates were kept need one.",
+        mutates: true,
+        side_effects: false,
+        interactive: false,
+        run: uncomplete
+    },
+    Command {
+        name: "undo",
+        aliases: &[],
+        params: &[],
+        help: "Reverts the last operation that changed classes.\nRun 'history' to see what can be undone.\n\nOnly the last 100 operations are kept.",
+        mutates: false,
+        side_effects: false,
+        interactive: true,
+        run: undo
+    },
+    Command {
+        name: "write",
+        aliases: &["w"],
+        params: &[],
+        help: "Writes encoded classes to the config file.\nThis is done automatically upon exit.",
+        mutates: false,
+        side_effects: true,
+        interactive: false,
+        run: write
+    }
+];
+
+fn to_int(arg: &str) -> Result<usize, String> {
+    arg.parse::<usize>().map_err(|e| e.to_string())
+}
+
+fn add(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    classes.check_active(&args.list[0])?;
+
+    let words = date_words(&args.list[1..], 1, Local::now().date_naive(), &settings().input_date_format);
+    let date = args.list[1..=words].join(" ");
+
+    // A due time can follow the date, as long as a name is left after it
+    let time = match args.list.get(words + 2) {
+        Some(_) => DueTime::parse(&args.list[words + 1]),
+        None => None
+    };
+
+    let name = args.input_from(words + 1 + time.is_some() as usize);
+    let d = Assignment::parse_date(&date)?;
+
+    let id = classes.next_assignment_id();
+    let c = classes.map.get_mut(&args.list[0]).unwrap();
+
+    println!("Due {}", style(Role::Date).paint(Assignment::display_due(d, time)));
+    c.add_assignment(id, name, d, time);
+    println!("\n{}", c.display_info());
+
+    Ok(())
+}
+
+fn all(_: &Args, classes: &mut Classes, session: &mut Session) -> Result<(), String> {
+    let queried = if session.changed { None } else { query_assignments() };
+
+    let due = queried.unwrap_or_else(|| classes.due_assignments());
+    println!("\n{}", classes.display_all_due(&due));
+
+    Ok(())
+}
+
+fn archive(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    if !args.check(1) {
+        println!("\n{}", classes.display_archive(None));
+        return Ok(());
+    }
+
+    if args.list[0] != "restore" {
+        if !classes.map.contains_key(&args.list[0]) {
+            return Err(format!("Class '{}' not found", args.list[0]));
+        }
+
+        println!("\n{}", classes.display_archive(Some(&args.list[0])));
+        return Ok(());
+    }
+
+    args.require(2)?;
+
+    let mut list = args.list[1..].to_vec();
+
+    // An archived assignment can be given by ID alone
+    if list.len() == 1 && !classes.map.contains_key(&list[0]) {
+        if let Some(c) = classes.find_archived(&list[0]) {
+            list.insert(0, c.id.clone());
+        }
+    }
+
+    if list.len() == 1 {
+        let name = classes.restore_class(&list[0])?;
+        success(format!("restored class '{}'", name));
+    } else {
+        let c = classes.map.get_mut(&list[0]).ok_or(format!("Class '{}' not found", list[0]))?;
+        let name = c.restore_archived(c.index_of(&list[1], List::Archive)?)?;
+        success(format!("restored '{}' to the completed list of '{}'", name, c.id));
+    }
+
+    Ok(())
+}
+
+fn clean(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let c = classes.get_class(args)?;
+    let count = c.clean();
+
+    println!("\n{}", c.display_info());
+    success(format!("archived {} completed assignment(s). See them with 'archive {}'.", count, c.id));
+
+    Ok(())
+}
+
+fn complete(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let c = classes.get_class(args)?;
+    c.complete_assignment(c.index_of(&args.list[1], List::Assignments)?)?;
+    println!("\n{}", c.display_info());
+
+    Ok(())
+}
+
+fn create(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let id = args.list[0].to_lowercase();
+
+    Classes::check_class_id(&id)?;
+
+    if classes.map.get(&id).is_some_and(|c| c.is_archived()) {
+        return Err(format!("Class ID '{}' belongs to an archived class. Restore it with 'archive restore {}' or remove it with 'purge {}'", id, id, id));
+    } else if classes.map.contains_key(&id) {
+        return Err(format!("Class ID '{}' already exists", id));
+    }
+
+    let name = args.input_from(2);
+
+    if name.trim().is_empty() {
+        return Err(String::from("Class name cannot be empty"));
+    }
+
+    classes.add_class_data(id.clone(), name, to_int(&args.list[1])?);
+    success(format!("created class '{}'", id));
+
+    Ok(())
+}
+
+fn delete(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let name = classes.archive_class(&args.list[0])?;
+    success(format!("deleted class '{}'. Restore it with 'archive restore {}'.", name, args.list[0]));
+
+    Ok(())
+}
+
+fn edit(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let c = classes.get_class(args)?;
+    let n = c.index_of(&args.list[1], List::Assignments)?;
+    let after = c.edited(n, &args.list[2], args.input_from(3))?;
+
+    let before = &c.assignments[n - 1];
+    let len = before.name.len().max(after.name.len());
+
+    println!("{} {}\n{}  {}",
+        style(Role::Label).paint("Before:"), before.display(0, len - before.name.len()),
+        style(Role::Label).paint("After:"), after.display(0, len - after.name.len()));
+
+    let name = after.name.clone();
+    c.assignments[n - 1] = after;
+    success(format!("edited '{}'", name));
+
+    Ok(())
+}
+
+fn encode(_: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    println!("\n{}", encode_config(classes)?);
+
+    Ok(())
+}
+
+fn format(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    if args.check(1) {
+        let f = Format::from_name(&args.list[0]).ok_or(format!("Invalid format '{}'", args.list[0]))?;
+        let path = convert_config(classes, f)?;
+        success(format!("moved data to '{}'", path.display()));
+    } else {
+        println!("\n{}\n{}",
+            Class::str_property("Format", &config_format().name().to_string()),
+            Class::str_property("File", &config_file().display().to_string()));
+    }
+
+    Ok(())
+}
+
+fn get(args: &Args, _: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let current = settings();
+
+    if args.check(1) {
+        let v = current.get(&args.list[0]).ok_or(format!("Invalid setting '{}'", args.list[0]))?;
+        println!("\n{}", Class::str_property(&args.list[0], &v));
+    } else {
+        let lines = KEYS.iter()
+            .map(|(k, d)| format!("{}\n  {}", Class::str_property(k, &current.get(k).unwrap()), d))
+            .collect::<Vec<String>>();
+
+        println!("\n{}", lines.join("\n"));
+    }
+
+    Ok(())
+}
+
+fn help(args: &Args, _: &mut Classes, _: &mut Session) -> Result<(), String> {
+    if args.check(1) {
+        let c = find(&args.list[0]).ok_or(format!("Command '{}' not found", args.list[0]))?;
+
+        println!("\n{}\n\n{}{}",
+            Class::str_property("Syntax", &c.syntax()),
+            c.help,
+            if c.aliases.is_empty() {
+                String::new()
+            } else {
+                format!("\n\n{}", Class::str_property("Aliases", &c.aliases.join(", ")))
+            }
+        );
+    } else {
+        let lines = COMMANDS.iter()
+            .map(|c| Class::info_property(c.name, &c.summary().to_string(), style(Role::Text)))
+            .collect::<Vec<String>>();
+
+        println!("\n{}", lines.join("\n"));
+    }
+
+    Ok(())
+}
+
+fn history(args: &Args, classes: &mut Classes, session: &mut Session) -> Result<(), String> {
+    if !args.check(1) {
+        println!("\n{}", session.history.display());
+        return Ok(());
+    }
+
+    match args.list[0].as_str() {
+        "search" => {
+            let lines = session.inputs.search(&args.input_from(1))
+                .into_iter()
+                .map(|(n, l)| format!("{}{} {}",
+                    style(Role::Number).paint(n.to_string()),
+                    style(Role::Muted).paint(")"),
+                    l))
+                .collect::<Vec<String>>();
+
+            if lines.is_empty() {
+                println!("\nNo matching commands");
+            } else {
+                println!("\n{}", lines.join("\n"));
+            }
+
+            Ok(())
+        }
+        "run" => {
+            args.require(2)?;
+
+            let n = to_int(&args.list[1])?;
+            let line = session.inputs.get(n).cloned().ok_or(format!("No command at number {}", n))?;
+
+            // Running a history command could run itself forever
+            if line.split_whitespace().next().and_then(find).map(|c| c.name) == Some("history") {
+                return Err(format!("Cannot run 'history' commands from history (number {})", n));
+            }
+
+            // Remember the command itself rather than this one
+            if session.inputs.lines.last().and_then(|l| l.split_whitespace().next()) == Some(args.command.as_str()) {
+                session.inputs.pop();
+            }
+
+            if let Err(e) = session.inputs.add(&line) {
+                err(e);
+            }
+
+            println!("{} {}", style(Role::Prompt).paint("=>"), line);
+
+            run_line(&line, classes, session)
+        }
+        _ => Err(format!("Invalid action '{}'. Use 'search' or 'run'.", args.list[0]))
+    }
+}
+
+fn info(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    if args.check(1) {
+        println!("\n{}", classes.get_class(args)?.display_info());
+    } else {
+        println!("\n{}", classes.display_all_info());
+    }
+
+    Ok(())
+}
+
+fn klog(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let data = classes.klog(to_int(&args.list[0])?);
+
+    if args.check(2) {
+        let mut path = PathBuf::from(args.list[1].clone());
+        path.set_extension("klg");
+
+        classes.write(path, data)?;
+    } else {
+        println!("\n{}", data);
+    }
+
+    Ok(())
+}
+
+fn late(_: &Args, classes: &mut Classes, session: &mut Session) -> Result<(), String> {
+    let late = late_list(classes, session);
+
+    if late.is_empty() {
+        println!("\nNo late assignments");
+    } else {
+        println!("\n{}", late.join("\n"));
+    }
+
+    Ok(())
+}
+
+fn list(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let sort = if args.check(1) {
+        SortingMethod::from_name(&args.list[0]).ok_or(format!("Invalid sorting method '{}'", args.list[0]))?
+    } else {
+        SortingMethod::preferred()
+    };
+
+    println!("\n{}", classes.display_list(sort));
+
+    Ok(())
+}
+
+fn modify(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let c = classes.get_class(args)?;
+    c.modify(args.list[1].clone(), args.input_from(2))?;
+    success(format!("modified '{}'", c.name));
+
+    Ok(())
+}
+
+fn move_assignment(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let (from, to) = (&args.list[0], &args.list[2]);
+    let completed = args.flag("completed");
+
+    let n = match classes.map.get(from) {
+        Some(c) => c.index_of(&args.list[1], if completed { List::Completed } else { List::Assignments })?,
+        None => to_int(&args.list[1])?
+    };
+
+    let name = classes.move_assignment(from, n, to, completed)?;
+    success(format!("moved '{}' from '{}' to '{}'", name, from, to));
+    println!("\n{}", classes.map[to].display_info());
+
+    Ok(())
+}
+
+fn panic(_: &Args, _: &mut Classes, session: &mut Session) -> Result<(), String> {
+    session.will_write = false;
+    success(String::from("prevented write on shutdown. None of the changes made during this session will be saved.\nTo view the encoded version of the changes you've made, run 'encode'."));
+
+    Ok(())
+}
+
+fn profile(args: &Args, classes: &mut Classes, session: &mut Session) -> Result<(), String> {
+    if !args.check(1) {
+        let active = active_profile();
+        let lines = list_profiles()
+            .iter()
+            .map(|p| if *p == active && !file_overridden() { style(Role::Text).paint(format!("* {}", p)).to_string() } else { format!("  {}", p) })
+            .collect::<Vec<String>>();
+
+        println!("\n{}", lines.join("\n"));
+
+        if file_overridden() {
+            println!("\nUsing data file '{}' instead of a profile", config_file().display());
+        }
+
+        return Ok(());
+    }
+
+    if args.list[0] == "create" {
+        args.require(2)?;
+        create_profile(&args.list[1], config_format())?;
+        success(format!("created profile '{}'", args.list[1]));
+
+        return Ok(());
+    }
+
+    let name = args.list[0].to_lowercase();
+    let previous = active_profile();
+
+    if file_overridden() {
+        return Err(String::from("Cannot switch profiles while using a data file set with '--file' or 'SKID_FILE'"));
+    }
+
+    valid_profile_name(&name)?;
+
+    if !profile_exists(&name) {
+        return Err(format!("Profile '{}' not found. Create it with 'profile create {}'", name, name));
+    } else if name == previous {
+        return Err(format!("Already using profile '{}'", name));
+    }
+
+    if session.will_write {
+        write_config(classes)?;
+    }
+
+    match set_active_profile(&name).map(|_| read_config())? {
+        Some(c) => {
+            *classes = c;
+            session.history.clear();
+            success(format!("switched to profile '{}'", name));
+
+            Ok(())
+        }
+        None => {
+            let _ = set_active_profile(&previous);
+            Err(format!("Could not load profile '{}'", name))
+        }
+    }
+}
+
+fn purge(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let mut list = args.list.clone();
+
+    // An archived assignment can be given by ID alone
+    if list.len() == 1 && !classes.map.contains_key(&list[0].to_lowercase()) {
+        if let Some(c) = classes.find_archived(&list[0]) {
+            list.insert(0, c.id.clone());
+        }
+    }
+
+    let id = list[0].to_lowercase();
+    let class = classes.map.get_mut(&id).ok_or(format!("Class '{}' not found", id))?;
+
+    if list.len() > 1 {
+        let d = class.take_archived(class.index_of(&list[1], List::Archive)?)?;
+        success(format!("purged '{}'", d.name));
+    } else if class.is_archived() {
+        let c = classes.remove_class(&id)?;
+        success(format!("purged class '{}'", c.name));
+    } else if args.flag("all") {
+        let count = class.archive.len();
+        class.archive.clear();
+        success(format!("purged {} archived assignment(s) from '{}'", count, id));
+    } else {
+        return Err(format!("'{}' isn't deleted. Give an index to purge one of its archived assignments, or run 'purge {} --all' to purge all {}.", id, id, class.archive.len()));
+    }
+
+    Ok(())
+}
+
+fn quit(_: &Args, _: &mut Classes, session: &mut Session) -> Result<(), String> {
+    session.quit = true;
+
+    Ok(())
+}
+
+fn redo(_: &Args, classes: &mut Classes, session: &mut Session) -> Result<(), String> {
+    let d = session.history.redo(classes)?;
+    success(format!("redid '{}'", d));
+
+    Ok(())
+}
+
+fn remove(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let c = classes.get_class(args)?;
+    c.remove_assignment(c.index_of(&args.list[1], List::Assignments)?)?;
+    println!("\n{}", c.display_info());
+
+    Ok(())
+}
+
+fn rename(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let id = classes.rename_class(&args.list[0], &args.list[1])?;
+    success(format!("renamed class '{}' to '{}'", args.list[0], id));
+
+    Ok(())
+}
+
+fn restore(args: &Args, classes: &mut Classes, session: &mut Session) -> Result<(), String> {
+    let backups = list_backups();
+
+    if args.check(1) {
+        let n = to_int(&args.list[0])?;
+        let b = n.checked_sub(1).and_then(|i| backups.get(i)).ok_or(format!("No backup at index {}", n))?;
+
+        *classes = load_config(&b.path, b.format)?;
+        session.history.clear();
+        write_config(classes)?;
+        success(format!("restored backup {}", n));
+    } else if backups.is_empty() {
+        println!("\nNo backups found");
+    } else {
+        let current = config_format();
+        let lines = backups.iter()
+            .enumerate()
+            .map(|(i, b)| format!("{}{} {}{}",
+                style(Role::Number).paint((i + 1).to_string()),
+                style(Role::Muted).paint(")"),
+                style(Role::Date).paint(b.time.format(&format!("{} %H:%M:%S", settings().display_date_format)).to_string()),
+                if b.format == current { String::new() } else { format!(" {}", style(Role::Muted).paint(format!("({})", b.format.name()))) }))
+            .collect::<Vec<String>>();
+
+        println!("\n{}", lines.join("\n"));
+    }
+
+    Ok(())
+}
+
+fn set(args: &Args, _: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let mut updated = settings();
+
+    updated.set(&args.list[0], args.input_from(1))?;
+    write_settings(&updated)?;
+    success(format!("set '{}' to '{}'", args.list[0], args.input_from(1)));
+
+    Ok(())
+}
+
+fn source(args: &Args, classes: &mut Classes, session: &mut Session) -> Result<(), String> {
+    let path = PathBuf::from(args.input_from(0));
+    let keep_going = args.flag("keep-going");
+
+    if args.flag("dry-run") {
+        dry_run(&path, classes, keep_going)
+    } else {
+        run_script(&path, classes, session, keep_going)
+    }
+}
+
+fn uncomplete(args: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    let c = classes.get_class(args)?;
+
+    let date = if args.check(3) {
+        Some(Assignment::parse_date(&args.input_from(2))?)
+    } else {
+        None
+    };
+
+    c.uncomplete_assignment(c.index_of(&args.list[1], List::Completed)?, date)?;
+    println!("\n{}", c.display_info());
+
+    Ok(())
+}
+
+fn undo(_: &Args, classes: &mut Classes, session: &mut Session) -> Result<(), String> {
+    let d = session.history.undo(classes)?;
+    success(format!("undid '{}'", d));
+
+    Ok(())
+}
+
+fn write(_: &Args, classes: &mut Classes, _: &mut Session) -> Result<(), String> {
+    write_config(classes)
+}
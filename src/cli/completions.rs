@@ -0,0 +1,171 @@
+extern crate clap_complete;
+extern crate clap_mangen;
+
+use clap_complete::{generate, Shell};
+use clap_mangen::Man;
+
+use super::app::app;
+use super::commands::COMMANDS;
+use super::registry::Param;
+
+pub const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "man"];
+
+/// Prints class IDs instead of a script, for the scripts themselves to use.
+pub const IDS: &str = "ids";
+
+/// Parameters completed with class IDs.
+const CLASS_PARAMS: [&str; 2] = ["class", "to-class"];
+
+/// Names and aliases of subcommands taking class IDs, with the IDs' positions.
+/// A keyword before a class can be left out, so the class can also come one position earlier.
+fn class_commands() -> Vec<(&'static str, Vec<usize>)> {
+    COMMANDS.iter()
+        .filter(|c| !c.interactive)
+        .map(|c| {
+            let params: Vec<&Param> = c.positionals().collect();
+
+            let positions = params.iter()
+                .enumerate()
+                .filter(|(_, p)| matches!(p, Param::Required(n) | Param::Optional(n) if CLASS_PARAMS.contains(n)))
+                .flat_map(|(i, _)| {
+                    let after_keyword = i > 0 && matches!(params[i - 1], Param::Keyword(_));
+                    if after_keyword { vec![i, i + 1] } else { vec![i + 1] }
+                })
+                .collect::<Vec<usize>>();
+
+            (c, positions)
+        })
+        .filter(|(_, positions)| !positions.is_empty())
+        .flat_map(|(c, positions)| std::iter::once(c.name).chain(c.aliases.iter().copied()).map(move |n| (n, positions.clone())))
+        .collect()
+}
+
+const ZSH_IDS: &str = r#"
+_skid_ids() {
+    local -a ids
+    ids=(${(f)"$(skid completions ids 2>/dev/null)"})
+    _describe 'class' ids
+}
+"#;
+
+/// Completes class IDs by asking skid for them, so they're always current.
+fn dynamic(shell: &str) -> String {
+    let commands = class_commands();
+
+    match shell {
+        "bash" => {
+            let cases = commands.iter()
+                .map(|(n, positions)| {
+                    let tests = positions.iter()
+                        .map(|i| format!("$arg -eq {}", i))
+                        .collect::<Vec<String>>()
+                        .join(" || ");
+
+                    format!("        {}) [[ {} ]] && ids=1 ;;", n, tests)
+                })
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            format!(r#"
+_skid_with_ids() {{
+    local i=1 ids=0
+
+    # Global options can come before the subcommand
+    while [[ $i -lt $COMP_CWORD ]]; do
+        case "${{COMP_WORDS[i]}}" in
+            -f|--file) i=$((i + 2)) ;;
+            -*) i=$((i + 1)) ;;
+            *) break ;;
+        esac
+    done
+
+    local arg=$((COMP_CWORD - i))
+
+    case "${{COMP_WORDS[i]}}" in
+{}
+    esac
+
+    if [[ $ids -eq 1 ]]; then
+        COMPREPLY=( $(compgen -W "$(skid completions ids 2>/dev/null)" -- "${{COMP_WORDS[COMP_CWORD]}}") )
+    else
+        _skid "$@"
+    fi
+}}
+
+complete -F _skid_with_ids -o bashdefault -o default skid
+"#, cases)
+        }
+        "fish" => commands.iter()
+            .flat_map(|(n, positions)| positions.iter().map(move |i| (n, i)))
+            .map(|(n, i)| format!("complete -c skid -f -n '__fish_seen_subcommand_from {}; and test (count (commandline -opc)) -eq {}' -a '(skid completions ids 2>/dev/null)'", n, i + 1))
+            .map(|l| format!("{}\n", l))
+            .collect(),
+        _ => String::new()
+    }
+}
+
+/// Generates a completion script for a shell, or the man page for `man`.
+pub fn completions(shell: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+
+    if shell == "man" {
+        Man::new(app())
+            .render(&mut out)
+            .map_err(|e| e.to_string())?;
+
+        return Ok(out);
+    }
+
+    let generator = match shell {
+        "bash" => Shell::Bash,
+        "zsh" => Shell::Zsh,
+        "fish" => Shell::Fish,
+        _ => return Err(format!("Invalid shell '{}'. Use {}.", shell, SHELLS.join(", ")))
+    };
+
+    generate(generator, &mut app(), "skid", &mut out);
+
+    let mut script = String::from_utf8(out).map_err(|e| e.to_string())?;
+
+    if shell == "zsh" {
+        script = script.replacen('\n', &format!("\n{}\n", ZSH_IDS), 1);
+
+        // Arguments without a value hint are completed with `_default`, given after the optional `:`s and the name
+        for name in CLASS_PARAMS {
+            for colons in [":", "::"] {
+                script = script.replace(&format!("'{}{}:_default'", colons, name), &format!("'{}{}:_skid_ids'", colons, name));
+            }
+        }
+    } else {
+        script.push_str(&dynamic(shell));
+    }
+
+    Ok(script.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(shell: &str) -> String {
+        String::from_utf8(completions(shell).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn zsh_completes_class_ids() {
+        let zsh = script("zsh");
+
+        assert!(zsh.contains("':class:_skid_ids'"));
+        assert!(zsh.contains("'::class:_skid_ids'"));
+        assert!(zsh.contains("'::to-class:_skid_ids'"));
+        assert!(!zsh.contains("class:_default"));
+    }
+
+    #[test]
+    fn bash_completes_archive_classes_with_or_without_restore() {
+        let bash = script("bash");
+
+        assert!(bash.contains("archive) [[ $arg -eq 1 || $arg -eq 2 ]] && ids=1 ;;"));
+        assert!(bash.contains("move) [[ $arg -eq 1 || $arg -eq 3 ]] && ids=1 ;;"));
+    }
+}
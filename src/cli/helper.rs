@@ -0,0 +1,179 @@
+extern crate ansi_term;
+extern crate rustyline;
+
+use std::borrow::Cow;
+
+use ansi_term::Style;
+use rustyline::{Context, Helper, Result};
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+
+use super::commands::COMMANDS;
+use super::registry::{complete, find, Param};
+use super::super::structs::{assignment::Assignment, class::Class};
+use super::super::util::{settings::{settings, style, Role, KEYS}, storage::Format};
+
+/// Line editor support for the interactive session, driven by the command registry.
+#[derive(Default)]
+pub struct SkidHelper {
+    /// Class IDs to complete, updated before each line is read.
+    pub ids: Vec<String>
+}
+
+/// The words before the cursor, and whether the last one is still being typed.
+fn words(line: &str) -> (Vec<&str>, bool) {
+    (line.split_whitespace().collect(), !line.is_empty() && !line.ends_with(' '))
+}
+
+impl SkidHelper {
+    /// Values a parameter can take, by its name.
+    fn values(&self, param: &Param) -> Vec<String> {
+        let strings = |list: &[&str]| list.iter().map(|s| String::from(*s)).collect();
+
+        match param {
+            Param::Keyword(k) => k.split('|').map(String::from).collect(),
+            Param::Required(n) | Param::Optional(n) => match *n {
+                "class" | "to-class" => self.ids.clone(),
+                "sort" => strings(&["id", "name", "period"]),
+                "property" => strings(&Class::PROPERTIES),
+                "field" => strings(&Assignment::FIELDS),
+                "setting" => KEYS.iter().map(|(k, _)| String::from(*k)).collect(),
+                "format" => Format::all().iter().map(|f| String::from(f.name())).collect(),
+                "command" => COMMANDS.iter().map(|c| String::from(c.name)).collect(),
+                _ => Vec::new()
+            },
+            _ => Vec::new()
+        }
+    }
+}
+
+impl Completer for SkidHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> Result<(usize, Vec<Pair>)> {
+        let (words, typing) = words(&line[..pos]);
+        let current = if typing { *words.last().unwrap() } else { "" };
+        let start = pos - current.len();
+
+        let candidates: Vec<String> = if words.is_empty() || (words.len() == 1 && typing) {
+            complete(current).into_iter().map(String::from).collect()
+        } else {
+            let command = match find(words[0]) {
+                Some(c) => c,
+                None => return Ok((start, Vec::new()))
+            };
+
+            if current.starts_with("--") {
+                command.flags().map(|f| format!("--{}", f)).collect()
+            } else {
+                // Count the values before this one, ignoring flags
+                let index = words[1..].iter()
+                    .filter(|w| !w.starts_with("--"))
+                    .count() - if typing { 1 } else { 0 };
+
+                match command.positionals().nth(index) {
+                    Some(p) => self.values(p),
+                    None => Vec::new()
+                }
+            }
+        };
+
+        let mut pairs: Vec<Pair> = candidates.into_iter()
+            .filter(|c| c.starts_with(current))
+            .map(|c| Pair { display: c.clone(), replacement: format!("{} ", c) })
+            .collect();
+
+        pairs.sort_by(|a, b| a.display.cmp(&b.display));
+
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for SkidHelper {
+    type Hint = String;
+
+    /// Shows the syntax of the rest of the command after the cursor.
+    fn hint(&self, line: &str, pos: usize, _: &Context<'_>) -> Option<String> {
+        if pos < line.len() {
+            return None;
+        }
+
+        let (words, typing) = words(line);
+
+        if words.is_empty() {
+            return None;
+        }
+
+        let command = find(words[0]);
+
+        if words.len() == 1 && typing {
+            return match command {
+                Some(c) if c.params.is_empty() => None,
+                Some(c) => Some(format!(" {}", c.params.iter().map(Param::syntax).collect::<Vec<String>>().join(" "))),
+                None => match complete(words[0]).as_slice() {
+                    [only] => Some(String::from(&only[words[0].len()..])),
+                    _ => None
+                }
+            };
+        }
+
+        if typing {
+            return None;
+        }
+
+        let command = command?;
+        let given = words[1..].iter().filter(|w| !w.starts_with("--")).count();
+
+        let mut rest: Vec<String> = command.positionals()
+            .skip(given)
+            .map(Param::syntax)
+            .collect();
+
+        rest.extend(command.flags()
+            .filter(|f| !words.contains(&format!("--{}", f).as_str()))
+            .map(|f| Param::Flag(f).syntax()));
+
+        if rest.is_empty() {
+            None
+        } else {
+            Some(rest.join(" "))
+        }
+    }
+}
+
+impl Highlighter for SkidHelper {
+    /// Marks the command in red if no command matches it.
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        let trimmed = line.trim_start();
+        let end = trimmed.find(' ').unwrap_or(trimmed.len());
+        let name = &trimmed[..end];
+
+        let invalid = !name.is_empty() && find(name).is_none() && (end < trimmed.len() || complete(name).is_empty());
+
+        if !invalid || !settings().color {
+            return Cow::Borrowed(line);
+        }
+
+        let offset = line.len() - trimmed.len();
+
+        Cow::Owned(format!("{}{}{}", &line[..offset], style(Role::Error).paint(name), &trimmed[end..]))
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        if settings().color {
+            Cow::Owned(Style::new().dimmed().paint(hint).to_string())
+        } else {
+            Cow::Borrowed(hint)
+        }
+    }
+
+    fn highlight_char(&self, _: &str, _: usize) -> bool {
+        true
+    }
+}
+
+impl Validator for SkidHelper {}
+
+impl Helper for SkidHelper {}
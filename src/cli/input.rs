@@ -1,16 +1,18 @@
-extern crate ansi_term;
 extern crate rustyline;
 
-use ansi_term::Color::Blue;
 use rustyline::{Editor, error::ReadlineError};
 
-use super::log::err;
+use std::io::{stdin, stdout, Write};
 
-#[derive(Debug)]
+use super::{helper::SkidHelper, log::err};
+use super::super::util::settings::{style, Role};
+
+#[derive(Debug, Clone)]
 pub struct Args {
     pub command: String,
     pub list: Vec<String>,
-    pub input: String
+    /// Flags given as `--name`, separated from the list when the command is validated.
+    pub flags: Vec<String>
 }
 
 impl Args {
@@ -18,54 +20,190 @@ impl Args {
         format!("(args: {})", self.list.join(", "))
     }
 
-    pub fn check(&self, min: usize, strict: bool) -> bool {
-        let result = self.list.len() >= min;
-        
-        if !result && strict { 
+    pub fn check(&self, min: usize) -> bool {
+        self.list.len() >= min
+    }
+
+    /// Fails unless at least `min` arguments were given.
+    pub fn require(&self, min: usize) -> Result<(), String> {
+        if self.check(min) {
+            Ok(())
+        } else {
             let name = if min != 1 { "arguments" } else { "argument" };
-            err(format!("Expected {} {} ({} provided)", min, name, self.list.len())) 
+            Err(format!("Expected {} {} ({} provided)", min, name, self.list.len()))
         }
-
-        result
     }
 
     pub fn input_from(&self, offset: usize) -> String {
         self.list[offset..].join(" ")
     }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+}
+
+/// Splits a line into words like a shell does. Quotes keep spaces inside a word:
+/// single quotes take everything literally, double quotes allow `\"` and `\\`,
+/// and a backslash outside of quotes escapes the next character.
+pub fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    // Whether a word has started, so quoted empty strings count as words
+    let mut started = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {
+                if started {
+                    words.push(std::mem::take(&mut word));
+                    started = false;
+                }
+            }
+            '\'' => {
+                started = true;
+
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(String::from("Missing closing quote (')"))
+                    }
+                }
+            }
+            '"' => {
+                started = true;
+
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(e) if e == '"' || e == '\\' => word.push(e),
+                            Some(e) => {
+                                word.push('\\');
+                                word.push(e);
+                            }
+                            None => return Err(String::from("Missing closing quote (\")"))
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(String::from("Missing closing quote (\")"))
+                    }
+                }
+            }
+            '\\' => {
+                started = true;
+                word.push(chars.next().unwrap_or('\\'));
+            }
+            _ => {
+                started = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if started {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Splits a command line into a command and its arguments.
+pub fn parse(line: &str) -> Result<Args, String> {
+    let mut words = tokenize(line)?.into_iter();
+
+    Ok(Args {
+        command: words.next().unwrap_or_default(),
+        list: words.collect(),
+        flags: Vec::new()
+    })
 }
 
-pub fn input(rl: &mut Editor<()>) -> Option<Args> {
+/// Reads a line, returning `None` when the session should end.
+pub fn input(rl: &mut Editor<SkidHelper>) -> Option<String> {
     use ReadlineError::*;
 
-    let prompt = format!("{} ", Blue.bold().paint("=>"));
+    let prompt = format!("{} ", style(Role::Prompt).paint("=>"));
 
     match rl.readline(prompt.as_str()) {
-        Ok(l) => {
-            let line = l.trim();
-            let a: Vec<&str> = line.split(" ").collect();
-
-            let command = String::from(a[0]);
-            let list: Vec<String> = (&a[1..])
-                .to_vec()
-                .iter()
-                .map(|s| String::from(*s))
-                .filter(|s| !s.is_empty())
-                .collect();
-
-            let input = list.join(" ");
-
-            rl.add_history_entry(line);
-
-            Some(Args {
-                command,
-                list,
-                input
-            })
-        }
+        Ok(l) => Some(String::from(l.trim())),
         Err(Interrupted) | Err(Eof) => None,
         Err(e) => {
             err(e.to_string());
             None
         }
     }
-}
\ No newline at end of file
+}
+
+/// Asks a question on stdin, returning `None` on EOF or a read error.
+pub fn prompt(question: &str) -> Option<String> {
+    print!("{} ", question);
+    let _ = stdout().flush();
+
+    let mut answer = String::new();
+
+    match stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(String::from(answer.trim()))
+    }
+}
+
+/// Asks a yes/no question on stdin, defaulting to no.
+pub fn confirm(question: &str) -> bool {
+    match prompt(&format!("{} {}", question, style(Role::Prompt).paint("[y/N]"))) {
+        Some(a) => matches!(a.to_lowercase().as_str(), "y" | "yes"),
+        None => false
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        tokenize(line).unwrap()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(words("  add math\t today  "), ["add", "math", "today"]);
+        assert!(words("   ").is_empty());
+    }
+
+    #[test]
+    fn quotes_keep_spaces() {
+        assert_eq!(words(r#"add math "Essay draft" 'Lab report'"#), ["add", "math", "Essay draft", "Lab report"]);
+    }
+
+    #[test]
+    fn single_quotes_are_literal() {
+        assert_eq!(words(r#"'a \" b' 'say "hi"'"#), [r#"a \" b"#, r#"say "hi""#]);
+    }
+
+    #[test]
+    fn double_quotes_allow_some_escapes() {
+        assert_eq!(words(r#""say \"hi\"" "a\\b" "c\d""#), [r#"say "hi""#, r"a\b", r"c\d"]);
+    }
+
+    #[test]
+    fn backslashes_escape_outside_quotes() {
+        assert_eq!(words(r"Essay\ draft it\'s end\"), ["Essay draft", "it's", r"end\"]);
+    }
+
+    #[test]
+    fn empty_quotes_are_words() {
+        assert_eq!(words(r#"edit 1 "" ''"#), ["edit", "1", "", ""]);
+    }
+
+    #[test]
+    fn adjacent_text_joins_into_one_word() {
+        assert_eq!(words(r#"pre"quoted part"'more'post"#), ["prequoted partmorepost"]);
+    }
+
+    #[test]
+    fn unterminated_quotes_are_errors() {
+        assert_eq!(tokenize("add 'Essay"), Err(String::from("Missing closing quote (')")));
+        assert_eq!(tokenize("add \"Essay"), Err(String::from("Missing closing quote (\")")));
+        assert_eq!(tokenize("add \"Essay\\"), Err(String::from("Missing closing quote (\")")));
+    }
+}
@@ -1,11 +1,13 @@
-extern crate ansi_term;
-
-use ansi_term::Color::Red;
+use super::super::util::settings::{style, Role};
 
 pub fn err(s: String) {
-    eprintln!("{} {}", Red.paint("ERR!"), s);
+    eprintln!("{} {}", style(Role::Error).paint("ERR!"), s);
+}
+
+pub fn warn(s: String) {
+    eprintln!("{} {}", style(Role::Warning).paint("WARN"), s);
 }
 
 pub fn success(s: String) {
     println!("Successfully {}", s);
-}
\ No newline at end of file
+}
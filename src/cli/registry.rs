@@ -0,0 +1,235 @@
+use super::{commands::COMMANDS, handler::Session, input::Args};
+use super::super::structs::classes::Classes;
+
+/// A command argument, in the order it's given.
+#[derive(Debug, Clone, Copy)]
+pub enum Param {
+    /// A value that must be given, shown as `<name>`.
+    Required(&'static str),
+    /// A value that can be left out, shown as `(<name>)`.
+    Optional(&'static str),
+    /// One or more words taking up the rest of the line, shown as `<name...>`.
+    Rest(&'static str),
+    /// Any number of words taking up the rest of the line, shown as `(<name...>)`.
+    More(&'static str),
+    /// A literal word that can be left out, shown as `(name)`.
+    /// Alternatives are separated by `|`.
+    Keyword(&'static str),
+    /// A switch given anywhere on the line, shown as `(--name)`.
+    Flag(&'static str)
+}
+
+impl Param {
+    pub fn syntax(&self) -> String {
+        match self {
+            Param::Required(n) => format!("<{}>", n),
+            Param::Optional(n) => format!("(<{}>)", n),
+            Param::Rest(n) => format!("<{}...>", n),
+            Param::More(n) => format!("(<{}...>)", n),
+            Param::Keyword(n) => format!("({})", n),
+            Param::Flag(n) => format!("(--{})", n)
+        }
+    }
+}
+
+pub type Run = fn(&Args, &mut Classes, &mut Session) -> Result<(), String>;
+
+/// Everything skid knows about a command, used for dispatch, help,
+/// argument validation, completion and the command line parser.
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub params: &'static [Param],
+    pub help: &'static str,
+    /// Changes classes, so it's recorded for undo.
+    pub mutates: bool,
+    /// Has effects outside the classes, so it's skipped in dry runs.
+    pub side_effects: bool,
+    /// Only makes sense in an interactive session, so it's not a subcommand.
+    pub interactive: bool,
+    pub run: Run
+}
+
+impl Command {
+    pub fn syntax(&self) -> String {
+        let params = self.params.iter()
+            .map(Param::syntax)
+            .collect::<Vec<String>>();
+
+        if params.is_empty() {
+            String::from(self.name)
+        } else {
+            format!("{} {}", self.name, params.join(" "))
+        }
+    }
+
+    /// The first line of the help text.
+    pub fn summary(&self) -> &'static str {
+        self.help.split('\n').next().unwrap()
+    }
+
+    /// Parameters given by position, skipping flags.
+    pub fn positionals(&self) -> impl Iterator<Item = &'static Param> + '_ {
+        self.params.iter().filter(|p| !matches!(p, Param::Flag(_)))
+    }
+
+    pub fn flags(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.params.iter().filter_map(|p| match p {
+            Param::Flag(n) => Some(*n),
+            _ => None
+        })
+    }
+
+    /// Whether the command starts with `<class> <index>`, which an assignment ID alone can replace.
+    pub fn takes_assignment(&self) -> bool {
+        let mut positionals = self.positionals();
+
+        matches!((positionals.next(), positionals.next()), (Some(Param::Required("class")), Some(Param::Required("index"))))
+    }
+
+    /// Puts the class in front of an assignment ID given without one.
+    pub fn expand_assignment(&self, args: &mut Args, classes: &Classes) {
+        if !self.takes_assignment() {
+            return;
+        }
+
+        if let Some(first) = args.list.first() {
+            if !classes.map.contains_key(first) {
+                if let Some(c) = classes.find_assignment(first) {
+                    args.list.insert(0, c.id.clone());
+                }
+            }
+        }
+    }
+
+    /// Separates flags from values and checks the number of values.
+    pub fn validate(&self, args: &mut Args) -> Result<(), String> {
+        let flags: Vec<&str> = self.flags().collect();
+
+        let (given, list): (Vec<String>, Vec<String>) = args.list
+            .drain(..)
+            .partition(|a| a.strip_prefix("--").is_some_and(|f| flags.contains(&f)));
+
+        args.list = list;
+        args.flags = given.into_iter().map(|f| String::from(&f[2..])).collect();
+
+        let min = self.params.iter().filter(|p| matches!(p, Param::Required(_) | Param::Rest(_))).count();
+        let max = if self.params.iter().any(|p| matches!(p, Param::Rest(_) | Param::More(_))) {
+            usize::MAX
+        } else {
+            self.positionals().count()
+        };
+
+        let count = args.list.len();
+        let plural = |n: usize| if n != 1 { "arguments" } else { "argument" };
+
+        if count < min {
+            Err(format!("Expected {} {} ({} provided)", min, plural(min), count))
+        } else if count > max {
+            Err(format!("Expected at most {} {} ({} provided)", max, plural(max), count))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Finds a command by name or alias.
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name || c.aliases.contains(&name))
+}
+
+/// Command names and aliases starting with `prefix`, in alphabetical order.
+pub fn complete(prefix: &str) -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = COMMANDS.iter()
+        .flat_map(|c| std::iter::once(c.name).chain(c.aliases.iter().copied()))
+        .filter(|n| n.starts_with(prefix))
+        .collect();
+
+    names.sort_unstable();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::input::parse;
+
+    fn run(_: &Args, _: &mut Classes, _: &mut Session) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn command(params: &'static [Param]) -> Command {
+        Command {
+            name: "test",
+            aliases: &[],
+            params,
+            help: "Tests.",
+            mutates: false,
+            side_effects: false,
+            interactive: false,
+            run
+        }
+    }
+
+    fn validate(params: &'static [Param], line: &str) -> Result<Args, String> {
+        let mut args = parse(line)?;
+        command(params).validate(&mut args).map(|_| args)
+    }
+
+    #[test]
+    fn missing_arguments_are_errors() {
+        let params = &[Param::Required("class"), Param::Required("index")];
+
+        assert!(validate(params, "test math 1").is_ok());
+        assert_eq!(validate(params, "test math").unwrap_err(), "Expected 2 arguments (1 provided)");
+        assert_eq!(validate(params, "test math 1 2").unwrap_err(), "Expected at most 2 arguments (3 provided)");
+        assert_eq!(validate(&[Param::Required("class")], "test").unwrap_err(), "Expected 1 argument (0 provided)");
+    }
+
+    #[test]
+    fn optional_arguments_can_be_left_out() {
+        let params = &[Param::Required("class"), Param::Optional("index")];
+
+        assert!(validate(params, "test math").is_ok());
+        assert!(validate(params, "test math 1").is_ok());
+        assert_eq!(validate(params, "test").unwrap_err(), "Expected 1 argument (0 provided)");
+        assert_eq!(validate(params, "test math 1 2").unwrap_err(), "Expected at most 2 arguments (3 provided)");
+    }
+
+    #[test]
+    fn rest_takes_at_least_one_word() {
+        let params = &[Param::Required("class"), Param::Rest("name")];
+
+        assert_eq!(validate(params, "test math Essay draft two").unwrap().list.len(), 4);
+        assert_eq!(validate(params, "test math").unwrap_err(), "Expected 2 arguments (1 provided)");
+        assert!(validate(&[Param::Required("class"), Param::More("name")], "test math").is_ok());
+    }
+
+    #[test]
+    fn flags_are_separated_from_values() {
+        let params = &[Param::Required("class"), Param::Flag("all")];
+        let args = validate(params, "test --all math").unwrap();
+
+        assert_eq!(args.list, ["math"]);
+        assert_eq!(args.flags, ["all"]);
+        assert_eq!(validate(params, "test math --other").unwrap_err(), "Expected at most 1 argument (2 provided)");
+    }
+
+    #[test]
+    fn assignment_commands_start_with_class_and_index() {
+        assert!(command(&[Param::Required("class"), Param::Required("index")]).takes_assignment());
+        assert!(command(&[Param::Flag("all"), Param::Required("class"), Param::Required("index"), Param::Rest("value")]).takes_assignment());
+        assert!(!command(&[Param::Required("class"), Param::Rest("name")]).takes_assignment());
+        assert!(!command(&[Param::Required("index"), Param::Required("class")]).takes_assignment());
+        assert!(!command(&[]).takes_assignment());
+    }
+
+    #[test]
+    fn commands_are_found_by_alias() {
+        assert_eq!(find("a").map(|c| c.name), Some("add"));
+        assert_eq!(find("ls").map(|c| c.name), Some("list"));
+        assert_eq!(find("list").map(|c| c.name), Some("list"));
+        assert!(find("nope").is_none());
+        assert!(find("complete").unwrap().takes_assignment());
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::{canonicalize, read_to_string};
+use std::path::Path;
+
+use super::{handler::{run_line, Session}, log::*};
+use super::super::structs::classes::Classes;
+use super::super::util::settings::{style, Role};
+
+/// Runs each line of a script as a command, skipping blank lines and `#` comments.
+/// Stops at the first failing line unless `keep_going` is set.
+/// Fails if any line did.
+pub fn run_script(path: &Path, classes: &mut Classes, session: &mut Session, keep_going: bool) -> Result<(), String> {
+    let data = read_to_string(path)
+        .map_err(|e| format!("Could not read script '{}': {}", path.display(), e))?;
+
+    let full = canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    if session.sourcing.contains(&full) {
+        return Err(format!("Script '{}' is already running", path.display()));
+    }
+
+    session.sourcing.push(full);
+
+    let mut failed = 0;
+    let mut total = 0;
+    let mut result = Ok(());
+
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        println!("{} {}", style(Role::Prompt).paint("=>"), line);
+
+        let ran = run_line(line, classes, session);
+
+        total += 1;
+        println!();
+
+        if let Err(e) = ran {
+            err(e);
+            failed += 1;
+
+            if !keep_going {
+                result = Err(format!("Stopped at line {} of '{}'", i + 1, path.display()));
+                break;
+            }
+        }
+
+        // Quitting ends the script, not the session running it
+        if std::mem::take(&mut session.quit) {
+            break;
+        }
+    }
+
+    session.sourcing.pop();
+
+    if failed > 0 && keep_going {
+        result = Err(format!("{} of {} commands in '{}' failed", failed, total, path.display()));
+    }
+
+    result
+}
+
+/// Runs a script against a copy of the classes and reports what would change.
+/// Commands with effects outside the classes are skipped.
+pub fn dry_run(path: &Path, classes: &Classes, keep_going: bool) -> Result<(), String> {
+    let mut working = classes.clone();
+    let mut session = Session::new();
+
+    session.dry_run = true;
+
+    let result = run_script(path, &mut working, &mut session, keep_going);
+    let mut changed = classes.changed_ids(&working);
+    changed.sort();
+
+    if changed.is_empty() {
+        println!("Dry run: no classes would change");
+    } else {
+        println!("Dry run: would change {}\n\n{}", changed.join(", "), session.history.display());
+    }
+
+    result
+}
@@ -2,12 +2,19 @@ mod cli;
 mod structs;
 mod util;
 
+extern crate clap;
 extern crate rustyline;
 
-use rustyline::Editor;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use rustyline::{Config, Editor};
 
 use util::config::*;
-use cli::handler::handler;
+use cli::{app::{app, args_from}, handler::{handler, dispatch, Session}, helper::SkidHelper, input::Args, log::err, script::{run_script, dry_run}, completions::{completions, IDS}};
+use structs::classes::Classes;
+use util::settings::settings;
 
 const HELP: &str = r#"
 skid - class assignment scheduler
@@ -22,22 +29,153 @@ and adding any assignments you have.
 
 Run 'quit' or press Ctrl+C to exit."#;
 
+/// Applies `--file <path>` or the `SKID_FILE` environment variable.
+fn apply_file(matches: &ArgMatches) {
+    let file = matches.get_one::<String>("file")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("SKID_FILE")
+            .filter(|f| !f.is_empty())
+            .map(PathBuf::from));
+
+    if let Some(f) = file {
+        set_config_file(f);
+    }
+}
+
+/// Reads the config for a command run from the command line, exiting if it can't be read.
+fn load() -> Classes {
+    if !config_exists() {
+        create_config();
+    }
+
+    match read_config() {
+        Some(c) => c,
+        None => {
+            err(format!("Fix the config file at '{}' and try again.", config_file().display()));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs a single command from the command line and exits.
+/// The config is written once, only if the command succeeded and changed something.
+fn run_command(args: Args) -> ! {
+    let mut classes = load();
+    let before = classes.clone();
+
+    let result = dispatch(&args, &mut classes, &mut Session::new()).and_then(|_| {
+        if before.changed_ids(&classes).is_empty() { Ok(()) } else { write_config(&mut classes) }
+    });
+
+    exit_with(result);
+}
+
+/// Runs a script from the command line and exits.
+/// Unless `--keep-going` is given, nothing is written if any command fails.
+fn run_file(matches: &ArgMatches) -> ! {
+    let path = PathBuf::from(matches.get_one::<String>("path").unwrap());
+    let keep_going = matches.get_flag("keep-going");
+
+    let mut classes = load();
+
+    let result = if matches.get_flag("dry-run") {
+        dry_run(&path, &classes, keep_going)
+    } else {
+        let before = classes.clone();
+        let ran = run_script(&path, &mut classes, &mut Session::new(), keep_going);
+
+        let written = if (ran.is_ok() || keep_going) && !before.changed_ids(&classes).is_empty() {
+            write_config(&mut classes)
+        } else {
+            Ok(())
+        };
+
+        ran.and(written)
+    };
+
+    exit_with(result);
+}
+
+/// Exits with a failing status and reports the error if there is one.
+fn exit_with(result: Result<(), String>) -> ! {
+    match result {
+        Ok(_) => std::process::exit(0),
+        Err(e) => {
+            err(e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_completions(shell: &str) -> ! {
+    match completions(shell) {
+        Ok(out) => {
+            let _ = std::io::stdout().write_all(&out);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            err(e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints class IDs for completion scripts, without prompting or printing errors.
+fn print_ids() -> ! {
+    if let Ok(classes) = load_config(&config_file(), config_format()) {
+        for id in classes.active_ids() {
+            println!("{}", id);
+        }
+    }
+
+    std::process::exit(0);
+}
+
 fn main() { 
     #[cfg(target_os = "windows")]
     ansi_term::enable_ansi_support();
 
+    let matches = app().get_matches();
+
+    apply_file(&matches);
+    read_settings();
+
+    match matches.subcommand() {
+        Some(("run", sub)) => run_file(sub),
+        Some(("completions", sub)) => match sub.get_one::<String>("shell").unwrap().as_str() {
+            IDS => print_ids(),
+            shell => print_completions(shell)
+        },
+        Some(_) => run_command(args_from(&matches).unwrap()),
+        None => {}
+    }
+
     if !config_exists() {
         println!("{}", HELP);
         create_config();
     }
 
-    let mut rl = Editor::<()>::new();
-    let mut classes = read_config();
+    let mut classes = match read_config() {
+        Some(c) => c,
+        None => {
+            println!("Fix the config file at '{}' and restart.", config_file().display());
+            std::process::exit(1);
+        }
+    };
+
+    let config = Config::builder()
+        .max_history_size(settings().history_size)
+        .build();
+
+    let mut rl = Editor::<SkidHelper>::with_config(config);
+    rl.set_helper(Some(SkidHelper::default()));
 
     let will_write = handler(&mut classes, &mut rl);
 
     if will_write {
-        write_config(&classes);
+        if let Err(e) = write_config(&mut classes) {
+            err(e);
+        }
     } else {
         println!();
     }
@@ -1,3 +1,6 @@
 pub mod assignment;
 pub mod class;
-pub mod classes;
\ No newline at end of file
+pub mod classes;
+pub mod completion;
+pub mod error;
+pub mod history;
\ No newline at end of file
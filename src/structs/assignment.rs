@@ -1,57 +1,259 @@
-extern crate ansi_term;
 extern crate chrono;
+extern crate serde;
 
-use ansi_term::Color::Green;
-use chrono::{offset::Local, Datelike, NaiveDate};
+use std::convert::TryFrom;
 
-use super::super::cli::log::err;
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use super::error::ParseError;
+use super::super::util::{dates::resolve, escape::{escape, unescape, split_unescaped}, settings::{settings, style, Role}};
+
+/// The date format used in the line encoding, independent of the input setting.
+pub const ENCODED_DATE_FORMAT: &str = "%d-%m-%Y";
+
+/// When on its due date an assignment is due.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum DueTime {
+    At(NaiveTime),
+    /// The start of a class period, timed by the `period_times` setting.
+    Period(usize)
+}
+
+impl DueTime {
+    /// Parses a time like `23:59`, `11:59pm` or `9am`, or a period like `p3`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+
+        if let Some(n) = s.strip_prefix('p') {
+            return n.parse::<usize>().ok().filter(|n| *n > 0).map(DueTime::Period);
+        }
+
+        let (body, offset) = match (s.strip_suffix("am"), s.strip_suffix("pm")) {
+            (Some(b), _) => (b, 0),
+            (_, Some(b)) => (b, 12),
+            _ => return NaiveTime::parse_from_str(&s, "%H:%M").ok().map(DueTime::At)
+        };
+
+        let (hour, minute) = match body.split_once(':') {
+            Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+            None => (body.parse::<u32>().ok()?, 0)
+        };
+
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+
+        NaiveTime::from_hms_opt(hour % 12 + offset, minute, 0).map(DueTime::At)
+    }
+
+    pub fn encode(&self) -> String {
+        match self {
+            DueTime::At(t) => t.format("%H:%M").to_string(),
+            DueTime::Period(n) => format!("p{}", n)
+        }
+    }
+
+    /// The time of day, if known. Periods without a start time in the settings have none.
+    pub fn time(&self) -> Option<NaiveTime> {
+        match self {
+            DueTime::At(t) => Some(*t),
+            DueTime::Period(n) => settings().period_time(*n)
+        }
+    }
+
+    pub fn display(&self) -> String {
+        match self {
+            DueTime::At(t) => t.format(&settings().display_time_format).to_string(),
+            DueTime::Period(n) => format!("period {}", n)
+        }
+    }
+}
+
+impl From<DueTime> for String {
+    fn from(t: DueTime) -> Self {
+        t.encode()
+    }
+}
+
+impl TryFrom<String> for DueTime {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        DueTime::parse(&s).ok_or_else(|| format!("Invalid due time '{}'", s))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Assignment {
+    /// A short ID like `a3`, unique across all classes and kept for the assignment's lifetime.
+    /// Empty until one is given by `Classes::assign_ids`.
+    #[serde(default)]
+    pub id: String,
     pub name: String,
-    pub due_date: NaiveDate
+    pub due_date: NaiveDate,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_time: Option<DueTime>
 }
 
 impl Assignment {
-    pub fn new(name: String, due_date: NaiveDate) -> Self {
+    pub fn new(id: String, name: String, due_date: NaiveDate, due_time: Option<DueTime>) -> Self {
         Assignment {
+            id,
             name,
             due_date,
+            due_time
         }
     }
 
-    pub fn display(&self, spaces: usize) -> String {
-        format!("{}{}- {}", 
-            Green.bold().paint(&self.name), 
+    /// The moment the assignment is due. Without a known time, that's the end of the due date.
+    pub fn due(&self) -> NaiveDateTime {
+        match self.due_time.and_then(|t| t.time()) {
+            Some(t) => self.due_date.and_time(t),
+            None => self.due_date.and_time(NaiveTime::MIN) + Duration::days(1)
+        }
+    }
+
+    pub fn is_late(&self, now: NaiveDateTime) -> bool {
+        self.due() <= now
+    }
+
+    /// The due date and time, formatted by the display settings.
+    pub fn display_due(due_date: NaiveDate, due_time: Option<DueTime>) -> String {
+        let date = due_date.format(&settings().display_date_format).to_string();
+
+        match due_time {
+            Some(t) => format!("{} {}", date, t.display()),
+            None => date
+        }
+    }
+
+    /// The number in an assignment ID, used to pick the next one.
+    pub fn id_number(id: &str) -> Option<usize> {
+        id.strip_prefix('a')?.parse().ok()
+    }
+
+    /// Displays the ID padded to `id_width`, and `spaces` more spaces after the name.
+    pub fn display(&self, id_width: usize, spaces: usize) -> String {
+        format!("{} {}{}- {}", 
+            style(Role::Muted).paint(format!("{:<1$}", self.id, id_width)),
+            style(Role::Text).paint(&self.name), 
             " ".repeat(spaces + 1), 
-            Green.paint(self.due_date.format("%b %e %Y").to_string())
+            style(Role::Date).paint(Self::display_due(self.due_date, self.due_time))
         )
     }
 
+    /// Fields that can be changed with `edit`.
+    pub const FIELDS: [&'static str; 3] = ["name", "date", "time"];
+
+    pub fn edit(&mut self, field: &str, value: String) -> Result<(), String> {
+        match field.to_lowercase().as_str() {
+            "name" if value.trim().is_empty() => Err(String::from("Assignment name cannot be empty")),
+            "name" => { self.name = value; Ok(()) },
+            "date" => { self.due_date = Self::parse_date(&value)?; Ok(()) },
+            "time" if value.to_lowercase() == "none" => { self.due_time = None; Ok(()) },
+            "time" => {
+                match DueTime::parse(&value) {
+                    Some(t) => { self.due_time = Some(t); Ok(()) },
+                    None => Err(format!("Invalid due time '{}'. Use a time like 23:59 or 11:59pm, a period like p3, or none.", value))
+                }
+            },
+            _ => Err(format!("Invalid field '{}'", field))
+        }
+    }
+
+    /// The encoded fields, without the brackets around them.
+    pub fn encode_fields(&self) -> String {
+        let time = self.due_time.map(|t| t.encode()).unwrap_or_default();
+
+        format!("{};{:0>2}-{:0>2}-{:4};{};{}", escape(&self.name), self.due_date.day(), self.due_date.month(), self.due_date.year(), time, escape(&self.id))
+    }
+
     pub fn encode(&self) -> String {
-        format!("[{};{:0>2}-{:0>2}-{:4}]", self.name, self.due_date.day(), self.due_date.month(), self.due_date.year())
+        format!("[{}]", self.encode_fields())
     }
 
+    /// Parses a date typed by the user, in the input date format setting
+    /// or written relative to today, like `tomorrow`, `fri` or `+3d`.
     pub fn parse_date(s: &str) -> Result<NaiveDate, String> {
-        match NaiveDate::parse_from_str(s, "%d-%m-%Y") {
+        match NaiveDate::parse_from_str(s, &settings().input_date_format) {
             Ok(d) => Ok(d),
-            Err(e) => Err(format!("Failed to parse date '{}': {}", s, e))
+            Err(e) => resolve(s, Local::now().date_naive())
+                .ok_or_else(|| format!("Failed to parse date '{}': {}", s, e))
         }
     }
 
-    pub fn parse(s: &str) -> Self {
-        let args: Vec<&str> = s[1..s.len() - 1].split(";").collect();
-        let due_date = match Self::parse_date(args[1]) {
-            Ok(d) => d,
-            Err(e) => {
-                err(e);
-                Local::today().naive_local()
-            }
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        if s.len() < 2 || !s.starts_with('[') || !s.ends_with(']') {
+            return Err(ParseError::new(1, format!("Malformed assignment '{}'", s)));
+        }
+
+        let body = &s[1..s.len() - 1];
+
+        Self::parse_fields(s, &split_unescaped(body, ';'))
+    }
+
+    /// Parses split fields of `s`, as written by `encode_fields`.
+    pub fn parse_fields(s: &str, args: &[&str]) -> Result<Self, ParseError> {
+        // Older versions leave out the ID, and the time when there is none
+        if args.len() < 2 || args.len() > 4 {
+            return Err(ParseError::new(1, format!("Expected assignment name, date, time and ID, found {} field(s)", args.len())));
+        }
+
+        let due_date = NaiveDate::parse_from_str(args[1], ENCODED_DATE_FORMAT)
+            .map_err(|e| ParseError::at(s, args[1], format!("Failed to parse date '{}': {}", args[1], e)))?;
+
+        let due_time = match args.get(2) {
+            Some(t) if !t.is_empty() => Some(DueTime::parse(t).ok_or_else(|| ParseError::at(s, t, format!("Invalid due time '{}'", t)))?),
+            _ => None
         };
 
-        Assignment {
-            name: String::from(args[0]),
-            due_date
+        Ok(Assignment {
+            id: args.get(3).map(|i| unescape(i)).unwrap_or_default(),
+            name: unescape(args[0]),
+            due_date,
+            due_time
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(h: u32, m: u32) -> Option<DueTime> {
+        Some(DueTime::At(NaiveTime::from_hms_opt(h, m, 0).unwrap()))
+    }
+
+    #[test]
+    fn twelve_hour_times_wrap_at_noon_and_midnight() {
+        assert_eq!(DueTime::parse("12am"), at(0, 0));
+        assert_eq!(DueTime::parse("12pm"), at(12, 0));
+        assert_eq!(DueTime::parse("11:59pm"), at(23, 59));
+        assert_eq!(DueTime::parse(" 9AM "), at(9, 0));
+        assert_eq!(DueTime::parse("13pm"), None);
+        assert_eq!(DueTime::parse("0am"), None);
+    }
+
+    #[test]
+    fn twenty_four_hour_times_parse() {
+        assert_eq!(DueTime::parse("23:59"), at(23, 59));
+        assert_eq!(DueTime::parse("00:00"), at(0, 0));
+        assert_eq!(DueTime::parse("24:00"), None);
+    }
+
+    #[test]
+    fn periods_start_from_one() {
+        assert_eq!(DueTime::parse("p3"), Some(DueTime::Period(3)));
+        assert_eq!(DueTime::parse("p0"), None);
+        assert_eq!(DueTime::parse("p-1"), None);
+        assert_eq!(DueTime::parse("p99999999999999999999999"), None);
+    }
+
+    #[test]
+    fn junk_is_rejected() {
+        for s in ["", "p", "pm", "noon", "12:xx", "12::30pm", "tomorrow"] {
+            assert_eq!(DueTime::parse(s), None, "{}", s);
         }
     }
-}
\ No newline at end of file
+}
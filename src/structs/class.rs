@@ -1,21 +1,37 @@
 extern crate ansi_term;
 extern crate chrono;
+extern crate serde;
 
 use std::collections::LinkedList;
 
-use ansi_term::{Style, Color::{Blue, Green, Yellow, Black}};
-use chrono::{NaiveDate, Local, Datelike};
+use ansi_term::Style;
+use chrono::{NaiveDate, NaiveDateTime, Local};
+use serde::{Deserialize, Serialize};
 
-use super::assignment::Assignment;
-use super::super::util::indent::indent_endl;
+use super::{assignment::{Assignment, DueTime}, completion::{Completion, ENCODED_TIME_FORMAT, RECORD_VERSION}, error::ParseError};
+use super::super::util::{indent::indent_endl, escape::{escape, unescape, split_unescaped}, settings::{style, Role}};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Class {
     pub id: String,
     pub name: String,
     pub period: usize,
     pub assignments: Vec<Assignment>,
-    pub completed: Vec<String>
+    pub completed: Vec<Completion>,
+    /// Completed assignments cleared by `clean`, kept until purged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archive: Vec<Completion>,
+    /// When the class was deleted. Deleted classes are kept in the archive until purged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<NaiveDateTime>
+}
+
+/// One of the lists of assignments in a class.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum List {
+    Assignments,
+    Completed,
+    Archive
 }
 
 impl Class {
@@ -25,32 +41,182 @@ impl Class {
             name,
             period,
             assignments: Vec::new(),
-            completed: Vec::new()
+            completed: Vec::new(),
+            archive: Vec::new(),
+            archived_at: None
         }
     }
 
-    pub fn add_assignment(&mut self, name: String, due_date: NaiveDate) {
-        self.assignments.push(Assignment::new(name, due_date));
+    pub fn add_assignment(&mut self, id: String, name: String, due_date: NaiveDate, due_time: Option<DueTime>) {
+        self.assignments.push(Assignment::new(id, name, due_date, due_time));
     }
 
-    pub fn remove_assignment(&mut self, index: usize) -> Result<String, String> {
-        if self.assignments.len() >= index {
-            let removed = self.assignments.remove(index - 1);
-            Ok(removed.name)
+    /// The position of an assignment in a list, given as a 1-based index or an assignment ID.
+    pub fn index_of(&self, s: &str, list: List) -> Result<usize, String> {
+        if let Ok(n) = s.parse::<usize>() {
+            return Ok(n);
+        }
+
+        let id = s.to_lowercase();
+
+        let (position, kind) = match list {
+            List::Assignments => (self.assignments.iter().position(|a| a.id == id), ""),
+            List::Completed => (self.completed.iter().position(|c| c.id == id), "completed "),
+            List::Archive => (self.archive.iter().position(|c| c.id == id), "archived ")
+        };
+
+        match position {
+            Some(i) => Ok(i + 1),
+            None => Err(format!("No {}assignment '{}' in class '{}'", kind, s, self.id))
+        }
+    }
+
+    pub fn take_assignment(&mut self, index: usize) -> Result<Assignment, String> {
+        if index > 0 && self.assignments.len() >= index {
+            Ok(self.assignments.remove(index - 1))
         } else {
             Err(format!("No assignment at index {}", index))
-        }   
+        }
+    }
+
+    pub fn remove_assignment(&mut self, index: usize) -> Result<String, String> {
+        self.take_assignment(index).map(|a| a.name)
     }
 
     pub fn complete_assignment(&mut self, index: usize) -> Result<(), String> {
-        match self.remove_assignment(index) {
-            Ok(name) => { self.completed.push(name); Ok(()) }
+        match self.take_assignment(index) {
+            Ok(a) => { self.completed.push(Completion::new(a)); Ok(()) }
             Err(e) => Err(e)
         }
     }
 
+    pub fn take_completed(&mut self, index: usize) -> Result<Completion, String> {
+        if index > 0 && self.completed.len() >= index {
+            Ok(self.completed.remove(index - 1))
+        } else {
+            Err(format!("No completed assignment at index {}", index))
+        }
+    }
+
+    /// Moves every completed assignment to the archive, returning how many were moved.
+    pub fn clean(&mut self) -> usize {
+        let count = self.completed.len();
+        self.archive.append(&mut self.completed);
+
+        count
+    }
+
+    pub fn take_archived(&mut self, index: usize) -> Result<Completion, String> {
+        if index > 0 && self.archive.len() >= index {
+            Ok(self.archive.remove(index - 1))
+        } else {
+            Err(format!("No archived assignment at index {}", index))
+        }
+    }
+
+    /// Puts an archived assignment back in the completed list, returning its name.
+    pub fn restore_archived(&mut self, index: usize) -> Result<String, String> {
+        let completion = self.take_archived(index)?;
+        let name = completion.name.clone();
+        self.completed.push(completion);
+
+        Ok(name)
+    }
+
+    /// IDs of every assignment in the class, pending, completed and archived, in that order.
+    pub fn assignment_ids(&self) -> Vec<&String> {
+        self.assignments.iter().map(|a| &a.id)
+            .chain(self.completed.iter().chain(self.archive.iter()).map(|d| &d.id))
+            .collect()
+    }
+
+    pub fn assignment_ids_mut(&mut self) -> Vec<&mut String> {
+        self.assignments.iter_mut().map(|a| &mut a.id)
+            .chain(self.completed.iter_mut().chain(self.archive.iter_mut()).map(|d| &mut d.id))
+            .collect()
+    }
+
+    /// IDs of the assignments in one list.
+    pub fn list_ids(&self, list: List) -> Vec<&String> {
+        match list {
+            List::Assignments => self.assignments.iter().map(|a| &a.id).collect(),
+            List::Completed => self.completed.iter().map(|d| &d.id).collect(),
+            List::Archive => self.archive.iter().map(|d| &d.id).collect()
+        }
+    }
+
+    /// Encodes the assignment at a 0-based position in a list.
+    pub fn encode_item(&self, list: List, index: usize) -> String {
+        match list {
+            List::Assignments => self.assignments[index].encode(),
+            List::Completed => self.completed[index].encode('+'),
+            List::Archive => self.archive[index].encode('-')
+        }
+    }
+
+    /// Copies the assignment at a 0-based position in a list to the end of the same list of `to`.
+    pub fn copy_item(&self, list: List, index: usize, to: &mut Class) {
+        match list {
+            List::Assignments => to.assignments.push(self.assignments[index].clone()),
+            List::Completed => to.completed.push(self.completed[index].clone()),
+            List::Archive => to.archive.push(self.archive[index].clone())
+        }
+    }
+
+    /// A copy of the class without any of its assignments.
+    pub fn details(&self) -> Class {
+        Class {
+            archived_at: self.archived_at,
+            ..Class::new(self.id.clone(), self.name.clone(), self.period)
+        }
+    }
+
+    /// Every assignment encoded, in the same order as `assignment_ids`.
+    pub fn encoded_assignments(&self) -> Vec<String> {
+        self.assignments.iter().map(|a| a.encode())
+            .chain(self.completed.iter().map(|d| d.encode('+')))
+            .chain(self.archive.iter().map(|d| d.encode('-')))
+            .collect()
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    /// Puts a completed assignment back at the end of the list, returning its name.
+    /// `due_date` replaces its due date, and is needed if none was kept.
+    pub fn uncomplete_assignment(&mut self, index: usize, due_date: Option<NaiveDate>) -> Result<String, String> {
+        let assignment = match self.completed.get(index.wrapping_sub(1)) {
+            Some(c) => c.assignment(due_date)
+                .map_err(|e| format!("{}. Give one after the index, like 'uncomplete {} {} fri'", e, self.id, index))?,
+            None => return Err(format!("No completed assignment at index {}", index))
+        };
+
+        self.completed.remove(index - 1);
+        let name = assignment.name.clone();
+        self.assignments.push(assignment);
+
+        Ok(name)
+    }
+
+    /// The assignment at `index` with a field changed, without changing the class.
+    pub fn edited(&self, index: usize, field: &str, value: String) -> Result<Assignment, String> {
+        match self.assignments.get(index.wrapping_sub(1)) {
+            Some(a) => {
+                let mut after = a.clone();
+                after.edit(field, value)?;
+                Ok(after)
+            },
+            None => Err(format!("No assignment at index {}", index))
+        }
+    }
+
+    /// Properties that can be changed with `modify`.
+    pub const PROPERTIES: [&'static str; 2] = ["name", "period"];
+
     pub fn modify(&mut self, property: String, value: String) -> Result<(), String> {
         match property.to_lowercase().as_str() {
+            "name" if value.trim().is_empty() => Err(String::from("Class name cannot be empty")),
             "name" => { self.name = value; Ok(()) },
             "period" => {
                 match value.parse::<usize>() {
@@ -62,22 +228,25 @@ impl Class {
         }
     }
 
+    /// Assignments past their due date and time as IDs and names, soonest due first.
     pub fn late(&self) -> Vec<String> {
-        let today = Local::today().naive_local().num_days_from_ce();
+        let now = Local::now().naive_local();
+
+        let mut late: Vec<&Assignment> = self.assignments.iter()
+            .filter(|a| a.is_late(now))
+            .collect();
 
-        self.assignments.iter()
-            .filter(|a| a.due_date.num_days_from_ce() < today)
-            .map(|a| a.name.clone())
-            .collect::<Vec<String>>()
+        late.sort_by_key(|a| a.due());
+        late.into_iter().map(|a| format!("{} {}", a.id, a.name)).collect()
     }
 
     pub fn assignment_list(assignments: &Vec<Assignment>, color: bool, start_from: usize) -> Vec<String> {
         let mut len: usize = 0;
+        let mut id_len: usize = 0;
 
         for s in assignments {
-            if s.name.len() > len {
-                len = s.name.len();
-            }
+            len = len.max(s.name.len());
+            id_len = id_len.max(s.id.len());
         }
 
         assignments
@@ -87,20 +256,30 @@ impl Class {
                 let n = (i + 1 + start_from).to_string();
 
                 format!("{}{} {}", 
-                    if color { Yellow.bold().paint(n).to_string() } else { n },
-                    if color { Black.bold().paint(")").to_string() } else { String::from(")") },
-                    a.display(len - a.name.len()))
+                    if color { style(Role::Number).paint(n).to_string() } else { n },
+                    if color { style(Role::Muted).paint(")").to_string() } else { String::from(")") },
+                    a.display(id_len, len - a.name.len()))
             })
             .collect()
     }
 
-    pub fn completed_list(completed: &Vec<String>) -> Vec<String> {
-        completed
-            .clone()
-            .into_iter()
+    pub fn bullet_list(items: &[String]) -> Vec<String> {
+        items
+            .iter()
             .map(|c| format!("{} {}", 
-                Black.bold().paint("-"),
-                Green.bold().paint(c)))
+                style(Role::Muted).paint("-"),
+                style(Role::Text).paint(c)))
+            .collect()
+    }
+
+    pub fn completed_list(completed: &[Completion]) -> Vec<String> {
+        completed
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{}{} {}",
+                style(Role::Number).paint((i + 1).to_string()),
+                style(Role::Muted).paint(")"),
+                c.display()))
             .collect()
     }
 
@@ -113,15 +292,15 @@ impl Class {
     }
 
     pub fn info_property(property: &str, value: &String, color: Style) -> String {
-        format!("{}: {}", Blue.paint(property), color.paint(value))
+        format!("{}: {}", style(Role::Label).paint(property), color.paint(value))
     }
 
     pub fn str_property(property: &str, value: &String) -> String {
-        Self::info_property(property, value, Green.bold())
+        Self::info_property(property, value, style(Role::Text))
     }
 
     pub fn int_property(property: &str, value: &String) -> String {
-        Self::info_property(property, value, Yellow.bold())
+        Self::info_property(property, value, style(Role::Number))
     }
 
     pub fn display(&self) -> String {
@@ -130,9 +309,9 @@ impl Class {
 
     pub fn display_all_fmt(assignments: String, completed: String) -> String {
         format!("{}: {}\n\n{}: {}", 
-            Blue.paint("Assignments"),
+            style(Role::Label).paint("Assignments"),
             Self::list_none(assignments), 
-            Blue.paint("Completed"),
+            style(Role::Label).paint("Completed"),
             Self::list_none(completed)
         )
     }
@@ -145,7 +324,7 @@ impl Class {
     }
 
     pub fn display_info_properties(&self) -> String {
-        vec![
+        [
             Self::str_property("Name", &self.name),
             Self::str_property("ID", &self.id),
             Self::int_property("Period", &self.period.to_string())
@@ -161,38 +340,122 @@ impl Class {
 
     pub fn encode(&self) -> String {
         let assignments: Vec<String> = self.assignments.iter().map(|a| a.encode()).collect();
-        let mut args: Vec<String> = vec![self.id.clone(), self.name.clone(), self.period.to_string()];
+        let completed: Vec<String> = self.completed.iter().map(|c| c.encode('+')).collect();
+        let archive: Vec<String> = self.archive.iter().map(|c| c.encode('-')).collect();
+        let mut args: Vec<String> = vec![escape(&self.id), escape(&self.name), self.period.to_string()];
 
         if !assignments.is_empty() { args.push(assignments.join(",")) }
-        if !self.completed.is_empty() { args.push(self.completed.join(",")) }
+        if !completed.is_empty() { args.push(completed.join(",")) }
+        if !archive.is_empty() { args.push(archive.join(",")) }
+        if let Some(at) = self.archived_at { args.push(format!("![{}]", at.format(ENCODED_TIME_FORMAT))) }
 
         args.join(",")
     }
 
-    pub fn parse(s: &str) -> Self {
-        let mut args: LinkedList<&str> = s.split(",").collect();
+    fn parse_field<'a>(s: &str, args: &mut LinkedList<&'a str>, field: &str) -> Result<&'a str, ParseError> {
+        match args.pop_front() {
+            Some(a) if !a.trim().is_empty() => Ok(a),
+            Some(a) => Err(ParseError::at(s, a, format!("Empty {}", field))),
+            None => Err(ParseError::new(s.chars().count() + 1, format!("Missing {}", field)))
+        }
+    }
+
+    /// Parses a class line written in format `version`.
+    pub fn parse(s: &str, version: usize) -> Result<Self, ParseError> {
+        let mut args: LinkedList<&str> = split_unescaped(s, ',').into_iter().collect();
+
+        let id = unescape(Self::parse_field(s, &mut args, "class ID")?);
+        let name = unescape(Self::parse_field(s, &mut args, "class name")?);
 
-        let id = String::from(args.pop_front().unwrap());
-        let name = String::from(args.pop_front().unwrap());
-        let period = args.pop_front().unwrap().parse::<usize>().unwrap();
+        let period_str = Self::parse_field(s, &mut args, "period")?;
+        let period = period_str.trim().parse::<usize>()
+            .map_err(|e| ParseError::at(s, period_str, format!("Invalid period '{}': {}", period_str, e)))?;
 
         let mut assignments: Vec<Assignment> = vec![];
-        let mut completed: Vec<String> = vec![];
+        let mut completed: Vec<Completion> = vec![];
+        let mut archive: Vec<Completion> = vec![];
+        let mut archived_at: Option<NaiveDateTime> = None;
 
         for a in args {
-            if a.trim().starts_with("[") {
-                assignments.push(Assignment::parse(a));
+            let trimmed = a.trim();
+            let column = ParseError::column_of(s, trimmed) - 1;
+
+            if trimmed.starts_with('[') {
+                assignments.push(Assignment::parse(trimmed).map_err(|e| e.shift(column))?);
+            } else if trimmed.starts_with("+[") {
+                completed.push(Completion::parse(trimmed, '+').map_err(|e| e.shift(column))?);
+            } else if trimmed.starts_with("-[") {
+                archive.push(Completion::parse(trimmed, '-').map_err(|e| e.shift(column))?);
+            } else if let Some(at) = trimmed.strip_prefix("![").and_then(|t| t.strip_suffix(']')) {
+                archived_at = Some(NaiveDateTime::parse_from_str(at, ENCODED_TIME_FORMAT)
+                    .map_err(|e| ParseError::at(s, at, format!("Failed to parse archive time '{}': {}", at, e)))?);
+            } else if version < RECORD_VERSION {
+                // Older versions only kept the names of completed assignments
+                completed.push(Completion::legacy(unescape(a)));
             } else {
-                completed.push(String::from(a));
+                return Err(ParseError::at(s, trimmed, format!("Unrecognized field '{}'", trimmed)));
             }
         }
 
-        Class {
+        Ok(Class {
             id,
             name,
             period,
             assignments,
-            completed
-        }
+            completed,
+            archive,
+            archived_at
+        })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::util::config::FORMAT_VERSION;
+
+    const AWKWARD: &str = "a,b;c[d]e\\f\ng\rh";
+
+    #[test]
+    fn encode_round_trips_special_characters() {
+        let date = NaiveDate::from_ymd_opt(2026, 11, 3).unwrap();
+        let mut class = Class::new(String::from(AWKWARD), String::from(AWKWARD), 2);
+
+        class.add_assignment(String::from("a1"), String::from(AWKWARD), date, None);
+        class.add_assignment(String::from("a2"), String::from("]["), date, DueTime::parse("p3"));
+        class.complete_assignment(2).unwrap();
+
+        let encoded = class.encode();
+        let parsed = Class::parse(&encoded, FORMAT_VERSION).unwrap();
+
+        assert!(!encoded.contains('\n'));
+        assert_eq!(parsed.id, AWKWARD);
+        assert_eq!(parsed.name, AWKWARD);
+        assert_eq!(parsed.assignments[0].name, AWKWARD);
+        assert_eq!(parsed.completed[0].name, "][");
+        assert_eq!(parsed.encode(), encoded);
+    }
+
+    #[test]
+    fn bare_names_are_completions_only_before_records() {
+        let line = "hw,Homework,1,done";
+        let parsed = Class::parse(line, RECORD_VERSION - 1).unwrap();
+
+        assert_eq!(parsed.completed[0].name, "done");
+        assert!(Class::parse(line, RECORD_VERSION).is_err());
+    }
+
+    #[test]
+    fn edited_leaves_the_class_alone() {
+        let date = NaiveDate::from_ymd_opt(2026, 11, 3).unwrap();
+        let mut class = Class::new(String::from("math"), String::from("Math"), 1);
+        class.add_assignment(String::from("a1"), String::from("Essay"), date, None);
+
+        let after = class.edited(1, "name", String::from("Essay draft")).unwrap();
+
+        assert_eq!(after.name, "Essay draft");
+        assert_eq!(class.assignments[0].name, "Essay");
+        assert!(class.edited(1, "name", String::from(" ")).is_err());
+        assert!(class.edited(2, "name", String::from("Lab")).is_err());
+    }
+}
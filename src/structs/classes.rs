@@ -1,13 +1,12 @@
-extern crate ansi_term;
-
-use ansi_term::Color::Purple;
+extern crate chrono;
 
 use std::collections::hash_map::HashMap;
-use std::fs::write;
 use std::path::PathBuf;
 
-use super::class::Class;
-use super::super::util::indent::indent_endl;
+use chrono::{Local, Timelike};
+
+use super::{assignment::Assignment, class::{Class, List}, error::ParseError};
+use super::super::util::{indent::indent_endl, atomic::write_atomic, settings::{settings, style, Role}};
 use super::super::cli::{log::*, input::Args};
 
 pub enum SortingMethod {
@@ -16,15 +15,76 @@ pub enum SortingMethod {
     Period
 }
 
-#[derive(Debug)]
+impl SortingMethod {
+    pub fn from_name(s: &str) -> Option<Self> {
+        use SortingMethod::*;
+
+        match s.to_lowercase().as_str() {
+            "id" => Some(ID),
+            "name" => Some(Name),
+            "period" => Some(Period),
+            _ => None
+        }
+    }
+
+    /// The sorting method from the settings.
+    pub fn preferred() -> Self {
+        Self::from_name(&settings().default_sort).unwrap_or(SortingMethod::Period)
+    }
+}
+
+/// What a three-way merge keeps of something changed in two versions.
+enum Pick {
+    Ours,
+    Theirs,
+    /// Each side added something different.
+    Both,
+    Conflict
+}
+
+impl Pick {
+    fn of<T: PartialEq>(base: &Option<T>, ours: &Option<T>, theirs: &Option<T>) -> Self {
+        if ours == theirs || theirs == base {
+            Pick::Ours
+        } else if ours == base {
+            Pick::Theirs
+        } else if base.is_none() {
+            Pick::Both
+        } else {
+            Pick::Conflict
+        }
+    }
+}
+
+/// Where an assignment is in one version of the classes.
+#[derive(Clone, Copy)]
+struct Placed<'a> {
+    class: &'a Class,
+    list: List,
+    index: usize,
+    /// Its position among all assignments in that version.
+    order: usize
+}
+
+impl Placed<'_> {
+    /// What's compared between versions of an assignment.
+    fn key(&self) -> (&str, List, String) {
+        (&self.class.id, self.list, self.class.encode_item(self.list, self.index))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Classes {
-    pub map: HashMap<String, Class>
+    pub map: HashMap<String, Class>,
+    /// The number of the next assignment ID, kept so IDs of removed assignments aren't reused.
+    pub next_id: usize
 }
 
 impl Classes {
     pub fn new() -> Self {
         Classes {
-            map: HashMap::new()
+            map: HashMap::new(),
+            next_id: 1
         }
     }
 
@@ -38,18 +98,58 @@ impl Classes {
     }
 
     pub fn get_class(&mut self, args: &Args) -> Result<&mut Class, String> {
-        match args.list.get(0) {
+        match args.list.first() {
             Some(id) => {
-                if !self.map.contains_key(id) {
-                    Err(format!("Class '{}' not found", id))
-                } else {
-                    Ok(self.map.get_mut(id).unwrap())
-                }
+                self.check_active(id)?;
+                Ok(self.map.get_mut(id).unwrap())
             }
             None => Err(format!("No ID provided {}", args.list()))
         }
     }
 
+    /// Checks that a class exists and hasn't been deleted to the archive.
+    pub fn check_active(&self, id: &str) -> Result<(), String> {
+        match self.map.get(id) {
+            Some(c) if c.is_archived() => Err(format!("Class '{}' is archived. Restore it with 'archive restore {}'", id, id)),
+            Some(_) => Ok(()),
+            None => Err(format!("Class '{}' not found", id))
+        }
+    }
+
+    /// IDs of the classes that aren't archived, sorted.
+    pub fn active_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.map.values()
+            .filter(|c| !c.is_archived())
+            .map(|c| c.id.clone())
+            .collect();
+
+        ids.sort();
+        ids
+    }
+
+    /// Moves a class to the archive, returning its name.
+    pub fn archive_class(&mut self, id: &str) -> Result<String, String> {
+        self.check_active(id)?;
+
+        let now = Local::now().naive_local();
+        let class = self.map.get_mut(id).unwrap();
+        class.archived_at = Some(now.with_nanosecond(0).unwrap_or(now));
+
+        Ok(class.name.clone())
+    }
+
+    /// Brings a class back from the archive, returning its name.
+    pub fn restore_class(&mut self, id: &str) -> Result<String, String> {
+        match self.map.get_mut(id) {
+            Some(c) if c.is_archived() => {
+                c.archived_at = None;
+                Ok(c.name.clone())
+            },
+            Some(_) => Err(format!("Class '{}' is not archived", id)),
+            None => Err(format!("Class '{}' not found", id))
+        }
+    }
+
     pub fn remove_class(&mut self, id: &String) -> Result<Class, String> {
         if !self.map.contains_key(id) {
             Err(format!("Class '{}' not found", id))
@@ -58,10 +158,167 @@ impl Classes {
         }
     }
 
-    pub fn sorted(&self, sort: SortingMethod) -> Vec<Class> {
+    fn highest_assignment_id(&self) -> usize {
+        self.map.values()
+            .flat_map(|c| c.assignment_ids())
+            .filter_map(|id| Assignment::id_number(id))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Takes a new assignment ID. IDs are never given out twice, even after removal.
+    pub fn next_assignment_id(&mut self) -> String {
+        let n = self.next_id.max(self.highest_assignment_id() + 1);
+        self.next_id = n + 1;
+
+        format!("a{}", n)
+    }
+
+    fn sorted_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.map.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Gives IDs to assignments without one, like those from older formats.
+    /// Classes are gone through by ID so the same data always gets the same IDs.
+    pub fn assign_ids(&mut self) {
+        self.next_id = self.next_id.max(self.highest_assignment_id() + 1);
+
+        for id in self.sorted_ids() {
+            for i in 0..self.map[&id].assignment_ids().len() {
+                if self.map[&id].assignment_ids()[i].is_empty() {
+                    let next = self.next_assignment_id();
+                    *self.map.get_mut(&id).unwrap().assignment_ids_mut()[i] = next;
+                }
+            }
+        }
+    }
+
+    /// Gives new IDs to assignments that share one, like those two sessions added at the same time.
+    /// An assignment that `keep` has unchanged in the same class keeps its ID. Returns the IDs that were changed.
+    pub fn dedupe_ids(&mut self, keep: &Classes) -> Vec<String> {
+        let mut owners: HashMap<String, (String, usize)> = HashMap::new();
+
+        for kept_first in [true, false] {
+            for c in self.sorted_ids() {
+                let kept: Vec<String> = keep.map.get(&c).map(|k| k.encoded_assignments()).unwrap_or_default();
+                let class = &self.map[&c];
+
+                for (i, (id, encoded)) in class.assignment_ids().into_iter().zip(class.encoded_assignments()).enumerate() {
+                    if !kept_first || kept.contains(&encoded) {
+                        owners.entry(id.clone()).or_insert((c.clone(), i));
+                    }
+                }
+            }
+        }
+
+        let mut changed: Vec<String> = Vec::new();
+
+        for c in self.sorted_ids() {
+            for i in 0..self.map[&c].assignment_ids().len() {
+                let id = self.map[&c].assignment_ids()[i].clone();
+
+                if owners[&id] != (c.clone(), i) {
+                    let next = self.next_assignment_id();
+                    changed.push(format!("{} -> {}", id, next));
+                    *self.map.get_mut(&c).unwrap().assignment_ids_mut()[i] = next;
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// The active class holding the assignment with an ID, whether it's completed or not.
+    pub fn find_assignment(&self, id: &str) -> Option<&Class> {
+        let id = id.to_lowercase();
+
+        self.map.values().find(|c| !c.is_archived() && (c.assignments.iter().any(|a| a.id == id)
+            || c.completed.iter().any(|d| d.id == id)))
+    }
+
+    /// The class holding the archived assignment with an ID.
+    pub fn find_archived(&self, id: &str) -> Option<&Class> {
+        let id = id.to_lowercase();
+
+        self.map.values().find(|c| c.archive.iter().any(|d| d.id == id))
+    }
+
+    /// Checks that a new class ID isn't blank and can't be mistaken for an assignment ID.
+    pub fn check_class_id(id: &str) -> Result<(), String> {
+        if id.trim().is_empty() {
+            Err(String::from("Class ID can't be empty"))
+        } else if Assignment::id_number(id).is_some() {
+            Err(format!("Class ID '{}' looks like an assignment ID. Pick one that isn't 'a' followed by a number", id))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Changes the ID of a class, lowercased like new IDs. Returns the new ID.
+    pub fn rename_class(&mut self, id: &str, new_id: &str) -> Result<String, String> {
+        let new_id = new_id.to_lowercase();
+
+        self.check_active(id)?;
+        Self::check_class_id(&new_id)?;
+
+        if new_id == id {
+            return Err(format!("Class '{}' already has that ID", id));
+        }
+
+        if self.map.contains_key(&new_id) {
+            return Err(format!("Class ID '{}' already exists", new_id));
+        }
+
+        let mut class = self.map.remove(id).unwrap();
+        class.id = new_id.clone();
+        self.add_class(new_id.clone(), class);
+
+        Ok(new_id)
+    }
+
+    /// Moves the assignment at `index` to another class, or the completed entry if `completed` is set.
+    /// Returns the name of what was moved.
+    pub fn move_assignment(&mut self, from: &str, index: usize, to: &str, completed: bool) -> Result<String, String> {
+        for id in [from, to] {
+            self.check_active(id)?;
+        }
+
+        if from == to {
+            return Err(format!("Assignment is already in class '{}'", to));
+        }
+
+        let source = self.map.get_mut(from).unwrap();
+
+        if completed {
+            let completion = source.take_completed(index)?;
+            let name = completion.name.clone();
+            self.map.get_mut(to).unwrap().completed.push(completion);
+
+            Ok(name)
+        } else {
+            let assignment = source.take_assignment(index)?;
+            let name = assignment.name.clone();
+            self.map.get_mut(to).unwrap().assignments.push(assignment);
+
+            Ok(name)
+        }
+    }
+
+    /// Classes that aren't archived, sorted.
+    pub fn sorted(&self, sort: SortingMethod) -> Vec<&Class> {
+        let mut values = self.sorted_all(sort);
+        values.retain(|c| !c.is_archived());
+
+        values
+    }
+
+    /// Every class, including archived ones, sorted.
+    pub fn sorted_all(&self, sort: SortingMethod) -> Vec<&Class> {
         use SortingMethod::*;
 
-        let mut values = self.map.values().cloned().collect::<Vec<Class>>();
+        let mut values = self.map.values().collect::<Vec<&Class>>();
 
         values.sort_by_key(|c| {
             match sort {
@@ -75,7 +332,7 @@ impl Classes {
     }
 
     pub fn attach_class(s: &String, c: &String) -> String {
-        format!("{} {}", s, Purple.bold().paint(format!("({})", c)))
+        format!("{} {}", s, style(Role::Class).paint(format!("({})", c)))
     }
 
     pub fn attach_class_tag(s: &String, c: &String) -> String {
@@ -88,39 +345,55 @@ impl Classes {
             .collect::<Vec<String>>()
     }
 
+    /// Formats assignments paired with their class IDs, as returned by storage queries.
+    pub fn due_list(due: &[(String, Assignment)]) -> Vec<String> {
+        due.iter()
+            .map(|(id, a)| Self::attach_class(&format!("{} {}", style(Role::Muted).paint("-"), style(Role::Text).paint(format!("{} {}", a.id, a.name))), id))
+            .collect()
+    }
+
+    /// Formats assignments paired with their class IDs along with when they're due.
+    pub fn dated_list(due: &[(String, Assignment)]) -> Vec<String> {
+        let id_len = due.iter().map(|(_, a)| a.id.len()).max().unwrap_or(0);
+        let len = due.iter().map(|(_, a)| a.name.len()).max().unwrap_or(0);
+
+        due.iter()
+            .map(|(id, a)| Self::attach_class(&format!("{} {}", style(Role::Muted).paint("-"), a.display(id_len, len - a.name.len())), id))
+            .collect()
+    }
+
     pub fn late(&self) -> Vec<String> {
         let mut result: Vec<String> = Vec::new();
 
-        for c in self.sorted(SortingMethod::Period) {
-            result.append(&mut Self::attach_class_items(Class::completed_list(&c.late()), &c));
+        for c in self.sorted(SortingMethod::preferred()) {
+            result.append(&mut Self::attach_class_items(Class::bullet_list(&c.late()), c));
         }
 
         result
     }
 
-    pub fn all_assignments(&self) -> Vec<String> {
-        let mut result: Vec<String> = Vec::new();
+    /// Assignments of active classes paired with their class IDs, ordered by when they're due
+    /// like a storage query.
+    pub fn due_assignments(&self) -> Vec<(String, Assignment)> {
+        let mut due: Vec<(String, Assignment)> = Vec::new();
 
-        for c in self.sorted(SortingMethod::Period) {
-            result.append(&mut Self::attach_class_items(Class::assignment_list(&c.assignments, true, result.len()), &c));
+        for id in self.active_ids() {
+            due.extend(self.map[&id].assignments.iter().map(|a| (id.clone(), a.clone())));
         }
 
-        result
+        due.sort_by_key(|(_, a)| a.due());
+        due
     }
     
     pub fn assignments_by_date(&self) -> HashMap<String, Vec<String>> {
         let mut result: HashMap<String, Vec<String>> = HashMap::new();
 
-        for c in self.sorted(SortingMethod::Period) {
-            for a in c.assignments {
+        for c in self.sorted(SortingMethod::preferred()) {
+            for a in &c.assignments {
                 let date = a.due_date.format("%Y-%m-%d").to_string();
                 let value = Self::attach_class_tag(&a.name, &c.id.clone());
 
-                if result.contains_key(&date) {
-                    result.get_mut(&date).unwrap().push(value);
-                } else {
-                    result.insert(date, vec![value]);
-                }
+                result.entry(date).or_default().push(value);
             }
         }
 
@@ -130,23 +403,58 @@ impl Classes {
     pub fn all_completed(&self) -> Vec<String> {
         let mut result: Vec<String> = Vec::new();
 
-        for c in self.sorted(SortingMethod::Period) {
-            result.append(&mut Self::attach_class_items(Class::completed_list(&c.completed), &c));
+        for c in self.sorted(SortingMethod::preferred()) {
+            result.append(&mut Self::attach_class_items(Class::completed_list(&c.completed), c));
         }
 
         result
     }
 
+    /// Deleted classes and cleaned assignments, or only those of one class.
+    pub fn display_archive(&self, id: Option<&str>) -> String {
+        let settings = settings();
+        let format = format!("{} {}", settings.display_date_format, settings.display_time_format);
+
+        let classes: Vec<&Class> = self.sorted_all(SortingMethod::preferred())
+            .into_iter()
+            .filter(|c| match id {
+                Some(id) => c.id == id,
+                None => true
+            })
+            .collect();
+
+        let deleted = classes.iter()
+            .filter_map(|c| c.archived_at.map(|at| format!("{} {} - {}",
+                style(Role::Muted).paint("-"),
+                c.display(),
+                style(Role::Date).paint(at.format(&format).to_string()))))
+            .collect::<Vec<String>>();
+
+        let mut assignments: Vec<String> = Vec::new();
+
+        for c in classes {
+            assignments.append(&mut Self::attach_class_items(Class::completed_list(&c.archive), c));
+        }
+
+        format!("{}: {}\n\n{}: {}",
+            style(Role::Label).paint("Deleted classes"),
+            Class::list_none(indent_endl(deleted, 2)),
+            style(Role::Label).paint("Cleaned assignments"),
+            Class::list_none(indent_endl(assignments, 2))
+        )
+    }
+
     pub fn display_all_info(&self) -> String {
-        self.sorted(SortingMethod::Period)
+        self.sorted(SortingMethod::preferred())
             .iter()
             .map(|c| c.display_info_properties())
             .collect::<Vec<String>>()
             .join("\n\n")
     }
 
-    pub fn display_all_combined(&self) -> String {
-        let assignments = indent_endl(self.all_assignments(), 2);
+    /// Every assignment ordered by when it's due, along with the completed ones.
+    pub fn display_all_due(&self, due: &[(String, Assignment)]) -> String {
+        let assignments = indent_endl(Self::dated_list(due), 2);
         let completed = indent_endl(self.all_completed(), 2);
 
         Class::display_all_fmt(assignments, completed)
@@ -179,28 +487,233 @@ impl Classes {
     }
 
     pub fn encode(&self) -> String {
-        self.sorted(SortingMethod::Period).iter().map(|c| c.encode()).collect::<Vec<String>>().join("\n")
+        self.sorted_all(SortingMethod::Period).iter().map(|c| c.encode()).collect::<Vec<String>>().join("\n")
     }
 
-    pub fn parse(s: String) -> Self {
-        let lines = s.split("\n").filter(|l| !l.is_empty());
-
-        let classes: Vec<Class> = lines.map(|l| Class::parse(l)).collect();
+    /// Parses every valid line of a format `version` body, collecting an error for each line that isn't.
+    pub fn parse_recover(s: &str, version: usize) -> (Self, Vec<ParseError>) {
         let mut map = HashMap::<String, Class>::new();
+        let mut errors: Vec<ParseError> = Vec::new();
 
-        for c in classes {
-            map.insert(c.id.clone(), c);
+        for (i, l) in s.split('\n').enumerate() {
+            let line = l.trim_end_matches('\r');
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match Class::parse(line, version) {
+                Ok(c) if map.contains_key(&c.id) => {
+                    errors.push(ParseError::new(1, format!("Duplicate class ID '{}'", c.id)).with_line(i + 1, line));
+                }
+                Ok(c) => {
+                    map.insert(c.id.clone(), c);
+                }
+                Err(e) => errors.push(e.with_line(i + 1, line))
+            }
         }
 
-        Classes {
-            map
+        (Classes { map, ..Classes::new() }, errors)
+    }
+
+    /// IDs of classes that differ between `self` and `other`, including added and removed classes.
+    pub fn changed_ids(&self, other: &Classes) -> Vec<String> {
+        let mut ids: Vec<String> = self.map.keys().chain(other.map.keys()).cloned().collect();
+
+        ids.sort();
+        ids.dedup();
+        ids.retain(|id| self.map.get(id).map(|c| c.encode()) != other.map.get(id).map(|c| c.encode()));
+
+        ids
+    }
+
+    /// Every assignment by ID, with where it is.
+    fn placements(&self) -> HashMap<&String, Placed<'_>> {
+        let mut result: HashMap<&String, Placed> = HashMap::new();
+
+        for id in self.sorted_ids() {
+            let class = &self.map[&id];
+
+            for list in [List::Assignments, List::Completed, List::Archive] {
+                for (index, a) in class.list_ids(list).into_iter().enumerate() {
+                    let order = result.len();
+                    result.entry(a).or_insert(Placed { class, list, index, order });
+                }
+            }
         }
+
+        result
+    }
+
+    /// Three-way merges two sets of changes made to `base`.
+    /// The details of classes and each assignment, found by its ID, are merged separately,
+    /// so changes to different assignments of a class are all kept. Anything changed
+    /// on both sides keeps `ours` unless `ours` removed it, and its ID is returned as a conflict.
+    pub fn merge(base: &Classes, ours: &Classes, theirs: &Classes) -> (Classes, Vec<String>) {
+        let mut result = Classes::new();
+        let mut conflicts: Vec<String> = Vec::new();
+
+        result.next_id = ours.next_id.max(theirs.next_id);
+
+        let mut ids: Vec<&String> = ours.map.keys().chain(theirs.map.keys()).collect();
+        ids.sort();
+        ids.dedup();
+
+        for id in ids {
+            let details = |c: &Classes| c.map.get(id).map(|c| c.details().encode());
+            let (o, t) = (ours.map.get(id), theirs.map.get(id));
+
+            let chosen = match Pick::of(&details(base), &details(ours), &details(theirs)) {
+                Pick::Ours => o,
+                Pick::Theirs => t,
+                Pick::Both | Pick::Conflict => {
+                    conflicts.push(id.clone());
+                    o.or(t)
+                }
+            };
+
+            if let Some(c) = chosen {
+                result.add_class(id.clone(), c.details());
+            }
+        }
+
+        let (b, o, t) = (base.placements(), ours.placements(), theirs.placements());
+
+        let mut ids: Vec<&String> = o.keys().chain(t.keys()).copied().collect();
+        ids.sort();
+        ids.dedup();
+
+        // Assignments are ordered as in `ours`, followed by those `theirs` added or moved
+        let mut chosen: Vec<((bool, usize), Placed)> = Vec::new();
+
+        for id in ids {
+            let (bp, op, tp) = (b.get(id), o.get(id), t.get(id));
+            let ours_key = |p: &Placed| (false, p.order);
+            let theirs_key = |p: &Placed| match op {
+                Some(q) if q.class.id == p.class.id && q.list == p.list => (false, q.order),
+                _ => (true, p.order)
+            };
+
+            match Pick::of(&bp.map(|p| p.key()), &op.map(|p| p.key()), &tp.map(|p| p.key())) {
+                Pick::Ours => chosen.extend(op.map(|p| (ours_key(p), *p))),
+                Pick::Theirs => chosen.extend(tp.map(|p| (theirs_key(p), *p))),
+                Pick::Both => {
+                    // Both sides gave a new assignment the same ID, which `dedupe_ids` sorts out
+                    chosen.extend(op.map(|p| (ours_key(p), *p)));
+                    chosen.extend(tp.map(|p| (theirs_key(p), *p)));
+                }
+                Pick::Conflict => {
+                    conflicts.push(id.clone());
+                    chosen.extend(op.map(|p| (ours_key(p), *p)).or_else(|| tp.map(|p| (theirs_key(p), *p))));
+                }
+            }
+        }
+
+        chosen.sort_by_key(|(k, _)| *k);
+
+        for (_, p) in chosen {
+            if !result.map.contains_key(&p.class.id) {
+                // One side removed the class while the other changed its assignments
+                conflicts.push(p.class.id.clone());
+                result.add_class(p.class.id.clone(), p.class.details());
+            }
+
+            p.class.copy_item(p.list, p.index, result.map.get_mut(&p.class.id).unwrap());
+        }
+
+        conflicts.sort();
+        conflicts.dedup();
+
+        (result, conflicts)
+    }
+
+    pub fn write(&self, path: PathBuf, data: String) -> Result<(), String> {
+        write_atomic(&path, &data).map_err(|e| e.to_string())?;
+        success(format!("wrote to '{}'", path.to_str().unwrap()));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn with_assignments(names: &[&str]) -> Classes {
+        let mut classes = Classes::new();
+        classes.add_class_data(String::from("math"), String::from("Math"), 1);
+
+        for name in names {
+            let id = classes.next_assignment_id();
+            let date = NaiveDate::from_ymd_opt(2026, 11, 3).unwrap();
+            classes.map.get_mut("math").unwrap().add_assignment(id, String::from(*name), date, None);
+        }
+
+        classes
+    }
+
+    #[test]
+    fn removed_ids_are_not_reused() {
+        let mut classes = with_assignments(&["one", "two", "three"]);
+        classes.map.get_mut("math").unwrap().remove_assignment(3).unwrap();
+
+        assert_eq!(classes.next_assignment_id(), "a4");
     }
 
-    pub fn write(&self, path: PathBuf, data: String) {
-        match write(path.clone(), data) {
-            Ok(_) => success(format!("wrote to '{}'", path.to_str().unwrap())),
-            Err(e) => err(e.to_string())
+    #[test]
+    fn dedupe_keeps_the_other_sessions_ids() {
+        let base = with_assignments(&["one"]);
+        let (mut ours, mut theirs) = (base.clone(), base.clone());
+
+        for (classes, name) in [(&mut ours, "ours"), (&mut theirs, "theirs")] {
+            let id = classes.next_assignment_id();
+            let date = NaiveDate::from_ymd_opt(2026, 11, 4).unwrap();
+            classes.map.get_mut("math").unwrap().add_assignment(id, String::from(name), date, None);
         }
+
+        let (mut merged, conflicts) = Classes::merge(&base, &ours, &theirs);
+
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.dedupe_ids(&theirs), ["a2 -> a3"]);
+
+        let names: Vec<(&str, &str)> = merged.map["math"].assignments.iter().map(|a| (a.id.as_str(), a.name.as_str())).collect();
+        assert_eq!(names, [("a1", "one"), ("a3", "ours"), ("a2", "theirs")]);
+    }
+    #[test]
+    fn merge_keeps_changes_to_different_assignments() {
+        let base = with_assignments(&["one", "two", "three"]);
+        let (mut ours, mut theirs) = (base.clone(), base.clone());
+
+        ours.map.get_mut("math").unwrap().assignments[0].name = String::from("first");
+        ours.map.get_mut("math").unwrap().assignments[2].name = String::from("ours");
+        theirs.map.get_mut("math").unwrap().complete_assignment(2).unwrap();
+        theirs.map.get_mut("math").unwrap().assignments[1].name = String::from("theirs");
+
+        let (merged, conflicts) = Classes::merge(&base, &ours, &theirs);
+        let math = &merged.map["math"];
+        let names: Vec<&str> = math.assignments.iter().map(|a| a.name.as_str()).collect();
+
+        assert_eq!(conflicts, ["a3"]);
+        assert_eq!(names, ["first", "ours"]);
+        assert_eq!(math.completed[0].name, "two");
     }
-}
\ No newline at end of file
+    #[test]
+    fn parse_errors_point_at_the_bad_field() {
+        let (classes, errors) = Classes::parse_recover("hw,Homework,1\n\nmath,,2\nsci,Science,x,[a;01-12-2026]", 7);
+        let found: Vec<(usize, usize, &str)> = errors.iter().map(|e| (e.line, e.column, e.message.as_str())).collect();
+
+        assert_eq!(classes.map.len(), 1);
+        assert_eq!(found, [(3, 6, "Empty class name"), (4, 13, "Invalid period 'x': invalid digit found in string")]);
+    }
+
+    #[test]
+    fn blank_class_names_are_rejected() {
+        let mut classes = with_assignments(&[]);
+        let math = classes.map.get_mut("math").unwrap();
+
+        assert!(math.modify(String::from("name"), String::from(" ")).is_err());
+        assert_eq!(math.name, "Math");
+    }
+}
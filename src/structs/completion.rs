@@ -0,0 +1,213 @@
+extern crate chrono;
+extern crate serde;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use super::{assignment::{Assignment, DueTime, ENCODED_DATE_FORMAT}, error::ParseError};
+use super::super::util::{escape::{escape, unescape, split_unescaped}, settings::{settings, style, Role}};
+
+/// The format of times in the line encoding.
+pub const ENCODED_TIME_FORMAT: &str = "%d-%m-%Y %H:%M";
+
+/// The format version that started keeping completed assignments as records instead of names.
+pub const RECORD_VERSION: usize = 5;
+
+/// A completed assignment, kept with when it was completed and whether it was late by then.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "Stored", from = "Stored")]
+pub struct Completion {
+    pub id: String,
+    pub name: String,
+    /// Unknown for assignments completed before due dates were kept.
+    pub due_date: Option<NaiveDate>,
+    pub due_time: Option<DueTime>,
+    /// Unknown for assignments completed before this was kept.
+    pub completed_at: Option<NaiveDateTime>,
+    pub late: bool
+}
+
+/// A completion as found in a JSON or TOML file, where older versions only kept the name.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Stored {
+    Name(String),
+    Record {
+        assignment: StoredAssignment,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        completed_at: Option<NaiveDateTime>,
+        #[serde(default)]
+        late: bool
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredAssignment {
+    #[serde(default)]
+    id: String,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due_date: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due_time: Option<DueTime>
+}
+
+impl From<Stored> for Completion {
+    fn from(s: Stored) -> Self {
+        match s {
+            Stored::Name(name) => Completion::legacy(name),
+            Stored::Record { assignment: a, completed_at, late } => Completion {
+                id: a.id,
+                name: a.name,
+                due_date: a.due_date,
+                due_time: a.due_time,
+                completed_at,
+                late
+            }
+        }
+    }
+}
+
+impl From<Completion> for Stored {
+    fn from(c: Completion) -> Self {
+        Stored::Record {
+            assignment: StoredAssignment { id: c.id, name: c.name, due_date: c.due_date, due_time: c.due_time },
+            completed_at: c.completed_at,
+            late: c.late
+        }
+    }
+}
+
+impl Completion {
+    /// Completes an assignment now.
+    pub fn new(assignment: Assignment) -> Self {
+        let now = Local::now().naive_local();
+        let now = now.with_nanosecond(0).unwrap_or(now);
+
+        Completion {
+            late: assignment.is_late(now),
+            completed_at: Some(now),
+            id: assignment.id,
+            name: assignment.name,
+            due_date: Some(assignment.due_date),
+            due_time: assignment.due_time
+        }
+    }
+
+    /// A completion from an older version, which only kept the name.
+    pub fn legacy(name: String) -> Self {
+        Completion {
+            id: String::new(),
+            name,
+            due_date: None,
+            due_time: None,
+            completed_at: None,
+            late: false
+        }
+    }
+
+    /// The assignment as it was before it was completed, due on `due_date` if given.
+    /// Fails if no due date is given and none was kept.
+    pub fn assignment(&self, due_date: Option<NaiveDate>) -> Result<Assignment, String> {
+        match due_date.or(self.due_date) {
+            Some(d) => Ok(Assignment::new(self.id.clone(), self.name.clone(), d, self.due_time)),
+            None => Err(format!("'{}' was completed before due dates were kept, so it needs one", self.name))
+        }
+    }
+
+    pub fn display(&self) -> String {
+        let mut s = format!("{} {}",
+            style(Role::Muted).paint(&self.id),
+            style(Role::Text).paint(&self.name));
+
+        if let Some(at) = self.completed_at {
+            let settings = settings();
+            let format = format!("{} {}", settings.display_date_format, settings.display_time_format);
+
+            s.push_str(&format!(" - {}", style(Role::Date).paint(at.format(&format).to_string())));
+        }
+
+        if self.late {
+            s.push_str(&format!(" {}", style(Role::Muted).paint("(late)")));
+        }
+
+        s
+    }
+
+    /// Encodes the completion, marked with `prefix` to tell which list it's in.
+    /// Unknown dates and times are left empty.
+    pub fn encode(&self, prefix: char) -> String {
+        let date = self.due_date.map(|d| d.format(ENCODED_DATE_FORMAT).to_string()).unwrap_or_default();
+        let time = self.due_time.map(|t| t.encode()).unwrap_or_default();
+        let at = self.completed_at.map(|t| t.format(ENCODED_TIME_FORMAT).to_string()).unwrap_or_default();
+
+        format!("{}[{};{};{};{};{};{}]", prefix, escape(&self.name), date, time, escape(&self.id), at, if self.late { "late" } else { "" })
+    }
+
+    pub fn parse(s: &str, prefix: char) -> Result<Self, ParseError> {
+        if s.len() < 3 || !s.starts_with(prefix) || !s[1..].starts_with('[') || !s.ends_with(']') {
+            return Err(ParseError::new(1, format!("Malformed completed assignment '{}'", s)));
+        }
+
+        let args: Vec<&str> = split_unescaped(&s[2..s.len() - 1], ';');
+
+        if args.len() != 6 {
+            return Err(ParseError::new(1, format!("Expected completed assignment and completion fields, found {} field(s)", args.len())));
+        }
+
+        let due_date = match args[1] {
+            "" => None,
+            d => Some(NaiveDate::parse_from_str(d, ENCODED_DATE_FORMAT)
+                .map_err(|e| ParseError::at(s, d, format!("Failed to parse date '{}': {}", d, e)))?)
+        };
+
+        let due_time = match args[2] {
+            "" => None,
+            t => Some(DueTime::parse(t).ok_or_else(|| ParseError::at(s, t, format!("Invalid due time '{}'", t)))?)
+        };
+
+        let completed_at = match args[4] {
+            "" => None,
+            t => Some(NaiveDateTime::parse_from_str(t, ENCODED_TIME_FORMAT)
+                .map_err(|e| ParseError::at(s, t, format!("Failed to parse completion time '{}': {}", t, e)))?)
+        };
+
+        Ok(Completion {
+            id: unescape(args[3]),
+            name: unescape(args[0]),
+            due_date,
+            due_time,
+            completed_at,
+            late: args[5] == "late"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_completions_keep_no_due_date() {
+        let encoded = Completion::legacy(String::from("done")).encode('+');
+        let parsed = Completion::parse(&encoded, '+').unwrap();
+
+        assert_eq!(encoded, "+[done;;;;;]");
+        assert_eq!(parsed.due_date, None);
+        assert!(parsed.assignment(None).is_err());
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let date = NaiveDate::from_ymd_opt(2026, 11, 3).unwrap();
+        let mut completion = Completion::new(Assignment::new(String::from("a4"), String::from("Essay; draft"), date, DueTime::parse("p2")));
+        completion.late = true;
+
+        let encoded = completion.encode('-');
+        let parsed = Completion::parse(&encoded, '-').unwrap();
+
+        assert_eq!(parsed.encode('-'), encoded);
+        assert_eq!(parsed.assignment(None).unwrap().due_date, date);
+        assert!(Completion::parse(&encoded, '+').is_err());
+    }
+}
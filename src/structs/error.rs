@@ -0,0 +1,71 @@
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    pub source: String
+}
+
+impl ParseError {
+    pub fn new(column: usize, message: String) -> Self {
+        ParseError {
+            line: 0,
+            column,
+            message,
+            source: String::new()
+        }
+    }
+
+    /// Creates an error pointing at `part`, which must be a slice of `whole`.
+    pub fn at(whole: &str, part: &str, message: String) -> Self {
+        Self::new(Self::column_of(whole, part), message)
+    }
+
+    /// The 1-based character column at which `part` starts within `whole`.
+    pub fn column_of(whole: &str, part: &str) -> usize {
+        let offset = (part.as_ptr() as usize).saturating_sub(whole.as_ptr() as usize).min(whole.len());
+        whole[..offset].chars().count() + 1
+    }
+
+    pub fn shift(mut self, columns: usize) -> Self {
+        self.column += columns;
+        self
+    }
+
+    pub fn with_line(mut self, line: usize, source: &str) -> Self {
+        self.line = line;
+        self.source = String::from(source);
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_count_characters_from_one() {
+        let line = "hw,Ünïcode,x";
+        let period = &line[line.rfind(',').unwrap() + 1..];
+
+        assert_eq!(ParseError::column_of(line, line), 1);
+        assert_eq!(ParseError::column_of(line, period), 12);
+        assert_eq!(ParseError::at(line, period, String::from("Bad")).column, 12);
+    }
+
+    #[test]
+    fn shifted_errors_report_their_line() {
+        let e = ParseError::new(3, String::from("Bad date")).shift(10).with_line(4, "source");
+
+        assert_eq!(e.to_string(), "line 4, column 13: Bad date");
+        assert_eq!(e.source, "source");
+    }
+}
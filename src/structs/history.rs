@@ -0,0 +1,235 @@
+use super::{class::Class, classes::Classes};
+use super::super::util::settings::{style, Role};
+
+/// How many operations can be undone. Each keeps whole classes, so older ones are dropped.
+pub const MAX_OPERATIONS: usize = 100;
+
+/// A change to one class, stored as its state before and after.
+/// `None` means the class didn't exist.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub id: String,
+    pub before: Option<Class>,
+    pub after: Option<Class>
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub description: String,
+    pub changes: Vec<Change>
+}
+
+impl Operation {
+    fn apply(&self, classes: &mut Classes, undo: bool) {
+        let changes: Box<dyn Iterator<Item = &Change>> = if undo {
+            Box::new(self.changes.iter().rev())
+        } else {
+            Box::new(self.changes.iter())
+        };
+
+        for c in changes {
+            let state = if undo { &c.before } else { &c.after };
+
+            match state {
+                Some(class) => classes.add_class(c.id.clone(), class.clone()),
+                None => {
+                    classes.map.remove(&c.id);
+                }
+            }
+        }
+    }
+}
+
+/// The operations performed during a session, for undo and redo.
+#[derive(Debug, Default)]
+pub struct History {
+    done: Vec<Operation>,
+    undone: Vec<Operation>
+}
+
+impl History {
+    /// Records the difference between `before` and `classes` as an operation.
+    /// Nothing is recorded if the command didn't change anything.
+    pub fn record(&mut self, description: String, before: &Classes, classes: &Classes) {
+        let changes: Vec<Change> = before.changed_ids(classes)
+            .into_iter()
+            .map(|id| Change {
+                before: before.map.get(&id).cloned(),
+                after: classes.map.get(&id).cloned(),
+                id
+            })
+            .collect();
+
+        if changes.is_empty() {
+            return;
+        }
+
+        self.done.push(Operation { description, changes });
+        self.undone.clear();
+
+        if self.done.len() > MAX_OPERATIONS {
+            self.done.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self, classes: &mut Classes) -> Result<String, String> {
+        match self.done.pop() {
+            Some(op) => {
+                op.apply(classes, true);
+                let description = op.description.clone();
+                self.undone.push(op);
+                Ok(description)
+            }
+            None => Err(String::from("Nothing to undo"))
+        }
+    }
+
+    pub fn redo(&mut self, classes: &mut Classes) -> Result<String, String> {
+        match self.undone.pop() {
+            Some(op) => {
+                op.apply(classes, false);
+                let description = op.description.clone();
+                self.done.push(op);
+                Ok(description)
+            }
+            None => Err(String::from("Nothing to redo"))
+        }
+    }
+
+    /// Forgets every operation, for when the classes are replaced entirely.
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    pub fn display(&self) -> String {
+        let mut lines: Vec<String> = self.done
+            .iter()
+            .enumerate()
+            .map(|(i, op)| format!("{}{} {}",
+                style(Role::Number).paint((i + 1).to_string()),
+                style(Role::Muted).paint(")"),
+                style(Role::Text).paint(&op.description)))
+            .collect();
+
+        for op in self.undone.iter().rev() {
+            lines.push(format!("{} {} {}",
+                style(Role::Muted).paint("-"),
+                op.description,
+                style(Role::Muted).paint("(undone)")));
+        }
+
+        if lines.is_empty() {
+            String::from("No operations this session")
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn math() -> Classes {
+        let mut classes = Classes::new();
+        classes.add_class_data(String::from("math"), String::from("Math"), 1);
+        classes
+    }
+
+    /// Renames math, recording it in `history`.
+    fn rename(history: &mut History, classes: &mut Classes, name: &str) {
+        let before = classes.clone();
+        classes.map.get_mut("math").unwrap().name = String::from(name);
+        history.record(format!("modify math name {}", name), &before, classes);
+    }
+
+    fn name(classes: &Classes) -> &str {
+        &classes.map["math"].name
+    }
+
+    #[test]
+    fn undo_reverts_a_change() {
+        let mut history = History::default();
+        let mut classes = math();
+
+        rename(&mut history, &mut classes, "Algebra");
+
+        assert_eq!(history.undo(&mut classes), Ok(String::from("modify math name Algebra")));
+        assert_eq!(name(&classes), "Math");
+        assert_eq!(history.undo(&mut classes), Err(String::from("Nothing to undo")));
+    }
+
+    #[test]
+    fn undo_removes_created_classes() {
+        let mut history = History::default();
+        let mut classes = Classes::new();
+
+        let before = classes.clone();
+        classes.add_class_data(String::from("math"), String::from("Math"), 1);
+        history.record(String::from("create math 1 Math"), &before, &classes);
+
+        history.undo(&mut classes).unwrap();
+        assert!(classes.map.is_empty());
+        history.redo(&mut classes).unwrap();
+        assert_eq!(name(&classes), "Math");
+    }
+
+    #[test]
+    fn redo_reapplies_an_undone_change() {
+        let mut history = History::default();
+        let mut classes = math();
+
+        rename(&mut history, &mut classes, "Algebra");
+        rename(&mut history, &mut classes, "Geometry");
+        history.undo(&mut classes).unwrap();
+        history.undo(&mut classes).unwrap();
+
+        assert_eq!(history.redo(&mut classes), Ok(String::from("modify math name Algebra")));
+        assert_eq!(name(&classes), "Algebra");
+        assert_eq!(history.redo(&mut classes), Ok(String::from("modify math name Geometry")));
+        assert_eq!(name(&classes), "Geometry");
+        assert_eq!(history.redo(&mut classes), Err(String::from("Nothing to redo")));
+    }
+
+    #[test]
+    fn new_changes_clear_redo() {
+        let mut history = History::default();
+        let mut classes = math();
+
+        rename(&mut history, &mut classes, "Algebra");
+        history.undo(&mut classes).unwrap();
+        rename(&mut history, &mut classes, "Geometry");
+
+        assert_eq!(history.redo(&mut classes), Err(String::from("Nothing to redo")));
+        assert_eq!(name(&classes), "Geometry");
+    }
+
+    #[test]
+    fn unchanged_classes_are_not_recorded() {
+        let mut history = History::default();
+        let mut classes = math();
+
+        let before = classes.clone();
+        history.record(String::from("list"), &before, &classes);
+
+        assert_eq!(history.undo(&mut classes), Err(String::from("Nothing to undo")));
+    }
+
+    #[test]
+    fn oldest_operations_are_dropped() {
+        let mut history = History::default();
+        let mut classes = math();
+
+        for i in 0..MAX_OPERATIONS + 5 {
+            rename(&mut history, &mut classes, &i.to_string());
+        }
+
+        for _ in 0..MAX_OPERATIONS {
+            history.undo(&mut classes).unwrap();
+        }
+
+        assert_eq!(name(&classes), "4");
+        assert_eq!(history.undo(&mut classes), Err(String::from("Nothing to undo")));
+    }
+}
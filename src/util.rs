@@ -1,2 +1,13 @@
+pub mod atomic;
+pub mod backup;
 pub mod config;
-pub mod indent;
\ No newline at end of file
+pub mod dates;
+pub mod escape;
+pub mod indent;
+pub mod input_history;
+pub mod lock;
+pub mod profile;
+pub mod settings;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod storage;
\ No newline at end of file
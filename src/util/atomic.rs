@@ -0,0 +1,27 @@
+use std::fs::{File, remove_file, rename};
+use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp", name))
+}
+
+/// Writes to a temporary file next to `path`, then renames it into place,
+/// so `path` holds either the old or the new contents, never a partial write.
+pub fn write_atomic(path: &Path, data: &str) -> Result<()> {
+    let temp = temp_path(path);
+
+    let result = File::create(&temp).and_then(|mut f| {
+        f.write_all(data.as_bytes())?;
+        f.sync_all()
+    });
+
+    match result.and_then(|_| rename(&temp, path)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = remove_file(&temp);
+            Err(e)
+        }
+    }
+}
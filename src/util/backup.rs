@@ -0,0 +1,87 @@
+extern crate chrono;
+
+use std::cmp::Reverse;
+use std::fs::{copy, metadata, read_dir, remove_file};
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDateTime};
+
+use super::{config::{config_file, format_file}, storage::Format};
+
+/// How many backups are kept next to the config before the oldest are removed.
+pub const BACKUP_COUNT: usize = 10;
+pub const BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub time: NaiveDateTime,
+    /// The format of the data file it was made from.
+    pub format: Format
+}
+
+impl Backup {
+    /// Reads a backup of the data file of any format, as they're kept after switching formats.
+    fn parse(path: PathBuf) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+
+        Format::all().into_iter().find_map(|format| {
+            let prefix = format!("{}.", format_file(format).file_name()?.to_str()?);
+            let time = name.strip_prefix(&prefix)?.strip_suffix(".bak")?;
+
+            NaiveDateTime::parse_from_str(time, BACKUP_TIME_FORMAT)
+                .ok()
+                .map(|time| Backup { path: path.clone(), time, format })
+        })
+    }
+}
+
+/// Lists the timestamped backups of the data file in every format, newest first.
+pub fn list_backups() -> Vec<Backup> {
+    let dir = match config_file().parent() {
+        Some(d) => d.to_path_buf(),
+        None => return Vec::new()
+    };
+
+    let mut backups: Vec<Backup> = match read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| Backup::parse(e.path()))
+            .collect(),
+        Err(_) => Vec::new()
+    };
+
+    backups.sort_by_key(|b| Reverse(b.time));
+    backups
+}
+
+/// A new timestamped backup path for a data file.
+pub fn backup_path(file: &Path) -> PathBuf {
+    let name = format!("{}.{}.bak",
+        file.file_name().unwrap().to_string_lossy(),
+        Local::now().format(BACKUP_TIME_FORMAT)
+    );
+
+    file.with_file_name(name)
+}
+
+/// Copies the current config to a new backup, then removes all but the newest `BACKUP_COUNT` backups.
+pub fn rotate_backups() -> Result<Option<PathBuf>, String> {
+    let config = config_file();
+
+    match metadata(&config) {
+        Ok(m) if m.len() > 0 => (),
+        _ => return Ok(None)
+    }
+
+    let path = backup_path(&config);
+
+    copy(&config, &path)
+        .map_err(|e| format!("Could not back up config file to '{}': {}", path.display(), e))?;
+
+    for b in list_backups().iter().skip(BACKUP_COUNT) {
+        let _ = remove_file(&b.path);
+    }
+
+    Ok(Some(path))
+}
@@ -1,14 +1,161 @@
+extern crate chrono;
 extern crate dirs;
+extern crate toml;
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
-use std::fs::{File, read_to_string};
+use std::fs::{OpenOptions, copy, read, read_to_string, rename};
+use std::io::Write;
+use std::sync::{Mutex, Once};
 
-use dirs::config_dir;
+use chrono::{Local, NaiveDate};
+use dirs::{config_dir, home_dir};
 
-use super::super::structs::classes::Classes;
+use super::super::structs::{classes::Classes, assignment::Assignment, error::ParseError};
+use super::super::cli::{input::{confirm, prompt}, log::{err, success, warn}};
+use super::{atomic::write_atomic, backup::{backup_path, rotate_backups, BACKUP_TIME_FORMAT}, lock::lock_config, settings::{Settings, set_settings}, profile::{active_profile, profile_stem}, storage::{Decoded, Format, Storage}};
 
+/// The current version of the config format, written as a header on the first line.
+///
+/// - 2 added the header and escaping.
+/// - 3 added an optional due time to assignments.
+/// - 4 added assignment IDs. Assignments without one are given one when loaded.
+/// - 5 keeps completed assignments as full records instead of names.
+/// - 6 added the archive, in new fields.
+/// - 7 keeps the next assignment ID in a header line, so removed IDs aren't reused,
+///   and leaves the due date of completions empty when it isn't known.
+pub const FORMAT_VERSION: usize = 7;
+pub const FORMAT_HEADER: &str = "#skid-format";
+/// The line after the format header holding the number of the next assignment ID.
+pub const NEXT_ID_HEADER: &str = "#skid-next-id";
+
+/// The data as last read or written by this session, and a hash of the file at that point,
+/// used to detect changes made by other sessions in the meantime.
+struct Loaded {
+    hash: u64,
+    classes: Classes
+}
+
+static LOADED: Mutex<Option<Loaded>> = Mutex::new(None);
+
+fn file_hash() -> u64 {
+    let mut hasher = DefaultHasher::new();
+    read(config_file()).unwrap_or_default().hash(&mut hasher);
+    hasher.finish()
+}
+
+fn set_loaded(classes: &Classes) {
+    *LOADED.lock().unwrap() = Some(Loaded { hash: file_hash(), classes: classes.clone() });
+}
+
+/// A data file set with `--file` or `SKID_FILE`, used instead of the active profile's.
+static FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+pub fn set_config_file(path: PathBuf) {
+    *FILE.lock().unwrap() = Some(path);
+}
+
+pub fn file_overridden() -> bool {
+    FILE.lock().unwrap().is_some()
+}
+
+/// The directory skid keeps its files in.
+/// Falls back to the home directory, then the working directory, on systems without a config directory.
+pub fn base_dir() -> PathBuf {
+    config_dir()
+        .or_else(home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+pub fn settings_file() -> PathBuf {
+    base_dir().join("skid-settings.toml")
+}
+
+pub fn history_file() -> PathBuf {
+    base_dir().join("skid-history")
+}
+
+/// Loads the settings file, keeping the defaults if it doesn't exist or is invalid.
+pub fn read_settings() {
+    let s = match read_to_string(settings_file()) {
+        Ok(s) => s,
+        Err(_) => return
+    };
+
+    let parsed = toml::from_str::<Settings>(&s)
+        .map_err(|e| e.to_string())
+        .and_then(|settings| settings.validate().map(|_| settings));
+
+    match parsed {
+        Ok(settings) => set_settings(settings),
+        Err(e) => err(format!("Could not read settings file '{}', using defaults: {}", settings_file().display(), e))
+    }
+}
+
+pub fn write_settings(settings: &Settings) -> Result<(), String> {
+    let data = toml::to_string_pretty(settings).map_err(|e| e.to_string())?;
+
+    write_atomic(&settings_file(), &data)
+        .map_err(|e| format!("Could not write settings file '{}': {}", settings_file().display(), e))?;
+
+    set_settings(settings.clone());
+    Ok(())
+}
+
+/// The data file path without a format extension.
+fn data_stem() -> PathBuf {
+    let file = FILE.lock().unwrap().clone();
+
+    match file {
+        Some(f) if f.extension().is_some() && Format::from_path(&f) != Format::Line => f.with_extension(""),
+        Some(f) => f,
+        None => profile_stem(&active_profile())
+    }
+}
+
+pub fn format_file(format: Format) -> PathBuf {
+    format.path(&data_stem())
+}
+
+/// The overridden data file if there is one, otherwise the first existing
+/// data file of the active profile, defaulting to the line format.
 pub fn config_file() -> PathBuf {
-    config_dir().unwrap().join("skid")
+    warn_unreadable_database();
+
+    let file = FILE.lock().unwrap().clone();
+
+    if let Some(f) = file {
+        return f;
+    }
+
+    Format::all().into_iter()
+        .map(format_file)
+        .find(|p| p.exists())
+        .unwrap_or_else(|| format_file(Format::Line))
+}
+
+/// Warns once if there's a SQLite data file, which builds without the `sqlite` feature can't read.
+fn warn_unreadable_database() {
+    static WARNED: Once = Once::new();
+
+    if Format::all().iter().any(|f| f.extension() == Some("db")) {
+        return;
+    }
+
+    let db = data_stem().with_extension("db");
+
+    if db.exists() {
+        WARNED.call_once(|| warn(format!("Ignoring '{}', as this build of skid can't read SQLite files. Build it with '--features sqlite' to use it.", db.display())));
+    }
+}
+
+pub fn config_format() -> Format {
+    Format::from_path(&config_file())
+}
+
+pub fn storage() -> Box<dyn Storage> {
+    config_format().storage()
 }
 
 pub fn config_exists() -> bool {
@@ -16,22 +163,364 @@ pub fn config_exists() -> bool {
 }
 
 pub fn create_config() {
-    match File::create(config_file()) {
-        Ok(_) => (),
-        Err(e) => println!("Could not create config file: {}", e)
+    if let Err(e) = storage().save(&config_file(), &Classes::new(), None) {
+        println!("Could not create config file: {}", e);
+    }
+}
+
+pub fn with_header(body: &str, next_id: usize) -> String {
+    format!("{} {}\n{} {}\n{}", FORMAT_HEADER, FORMAT_VERSION, NEXT_ID_HEADER, next_id, body)
+}
+
+pub fn encode_config(classes: &Classes) -> Result<String, String> {
+    config_format().text_storage().encode(classes)
+}
+
+pub fn write_config(classes: &mut Classes) -> Result<(), String> {
+    let _lock = lock_config()?;
+
+    let loaded = LOADED.lock().unwrap().take();
+
+    let mut conflicts: Vec<String> = Vec::new();
+
+    let previous = match loaded {
+        Some(l) if l.hash != file_hash() => match resolve_conflict(classes, &l.classes) {
+            Some((current, c)) => {
+                conflicts = c;
+                Some(current)
+            },
+            None => {
+                *LOADED.lock().unwrap() = Some(l);
+                return Err(String::from("Aborted write. Run 'write' to try again, or 'panic' to discard this session's changes."));
+            }
+        },
+        Some(l) => Some(l.classes),
+        None => None
+    };
+
+    let changed = match &previous {
+        Some(p) => !p.changed_ids(classes).is_empty(),
+        None => true
+    };
+
+    if changed {
+        match rotate_backups() {
+            Ok(Some(backup)) if !conflicts.is_empty() => {
+                println!("The other session's version of {} is kept in the backup at '{}'", conflicts.join(", "), backup.display());
+            }
+            Ok(_) => (),
+            Err(e) => err(e)
+        }
+    }
+
+    let path = config_file();
+
+    match storage().save(&path, classes, previous.as_ref()) {
+        Ok(_) => {
+            success(format!("wrote to '{}'", path.display()));
+            set_loaded(classes);
+
+            Ok(())
+        }
+        Err(e) => {
+            if let Some(p) = previous {
+                *LOADED.lock().unwrap() = Some(Loaded { hash: file_hash(), classes: p });
+            }
+
+            Err(format!("Could not write '{}': {}", path.display(), e))
+        }
+    }
+}
+
+/// Handles a config that another session changed since this one loaded it.
+/// Returns the data currently on disk if the write should go ahead,
+/// along with the IDs of classes and assignments whose changes from the other session were dropped.
+fn resolve_conflict(classes: &mut Classes, base: &Classes) -> Option<(Classes, Vec<String>)> {
+    let current = match load_config(&config_file(), config_format()) {
+        Ok(c) => c,
+        Err(e) => {
+            err(format!("The config was changed by another session and can't be read: {}", e));
+            return if confirm("Overwrite it?") { Some((Classes::new(), Vec::new())) } else { None };
+        }
+    };
+
+    let changed = base.changed_ids(&current);
+
+    let question = format!("\nThe config at '{}' was changed by another session since it was loaded.\nChanged classes: {}\n[m]erge, [o]verwrite or [a]bort?",
+        config_file().display(),
+        if changed.is_empty() { String::from("none") } else { changed.join(", ") }
+    );
+
+    loop {
+        let answer = match prompt(&question) {
+            Some(a) => a.to_lowercase(),
+            None => String::from("a")
+        };
+
+        match answer.as_str() {
+            "m" | "merge" => {
+                let (mut merged, conflicts) = Classes::merge(base, classes, &current);
+                let renamed = merged.dedupe_ids(&current);
+
+                if !conflicts.is_empty() {
+                    println!("Both sessions changed {}. Kept this session's version.", conflicts.join(", "));
+                }
+
+                if !renamed.is_empty() {
+                    println!("Both sessions used the same assignment IDs. Changed this session's: {}", renamed.join(", "));
+                }
+
+                *classes = merged;
+                return Some((current, conflicts));
+            }
+            "o" | "overwrite" => return Some((current, changed)),
+            "a" | "abort" => return None,
+            _ => err(format!("Invalid option '{}'", answer))
+        }
+    }
+}
+
+/// Splits a config into its format version and body.
+/// Files without a header are version 1.
+pub fn split_version(s: &str) -> Result<(usize, &str), String> {
+    if s.trim().is_empty() {
+        return Ok((FORMAT_VERSION, ""));
+    }
+
+    if !s.starts_with(FORMAT_HEADER) {
+        return Ok((1, s));
+    }
+
+    let (header, body) = s.split_at(s.find('\n').unwrap_or(s.len()));
+    let value = header[FORMAT_HEADER.len()..].trim();
+
+    match value.parse::<usize>() {
+        Ok(0) => Err(String::from("Invalid format version 0")),
+        Ok(v) if v > FORMAT_VERSION => Err(format!("Format version {} is newer than this version of skid supports ({})", v, FORMAT_VERSION)),
+        Ok(v) => Ok((v, body.strip_prefix('\n').unwrap_or(body))),
+        Err(e) => Err(format!("Invalid format version '{}': {}", value, e))
+    }
+}
+
+/// Splits the next assignment ID line, if there is one, from the rest of a config body.
+pub fn split_next_id(body: &str) -> Result<(Option<usize>, &str), String> {
+    if !body.starts_with(NEXT_ID_HEADER) {
+        return Ok((None, body));
+    }
+
+    let (line, rest) = body.split_at(body.find('\n').unwrap_or(body.len()));
+    let value = line[NEXT_ID_HEADER.len()..].trim();
+
+    match value.parse::<usize>() {
+        Ok(n) => Ok((Some(n), rest.strip_prefix('\n').unwrap_or(rest))),
+        Err(e) => Err(format!("Invalid next assignment ID '{}': {}", value, e))
+    }
+}
+
+/// A new backup path for a config in an older format version.
+/// It's timestamped so later upgrades don't overwrite it, and kept out of the rotation.
+pub fn backup_file(version: usize) -> PathBuf {
+    let config = config_file();
+    let name = format!("{}.v{}.{}.bak", config.file_name().unwrap().to_string_lossy(), version, Local::now().format(BACKUP_TIME_FORMAT));
+    config.with_file_name(name)
+}
+
+/// Upgrades a config body from `version` to the current format.
+/// Later versions only added fields, which are read according to the version,
+/// so only version 1 needs rewriting.
+pub fn migrate(version: usize, body: &str) -> String {
+    match version {
+        // Version 1 had no escaping, so every backslash was part of a name.
+        // Doubling them keeps them literal once escapes are read.
+        1 => body.replace('\\', "\\\\"),
+        _ => String::from(body)
+    }
+}
+
+/// Backs up an outdated config file, then rewrites it in the current format.
+fn upgrade_config(classes: &Classes, version: usize) -> Result<(), String> {
+    let backup = backup_file(version);
+
+    copy(config_file(), &backup)
+        .map_err(|e| format!("Could not back up config file to '{}': {}", backup.display(), e))?;
+
+    storage().save(&config_file(), classes, None)
+        .map_err(|e| format!("Could not write upgraded config file: {}", e))?;
+
+    println!("Upgraded config from format v{} to v{} (backup at '{}')", version, FORMAT_VERSION, backup.display());
+
+    Ok(())
+}
+
+/// Strictly loads a data file in `format`, migrating it in memory if needed.
+pub fn load_config(path: &Path, format: Format) -> Result<Classes, String> {
+    let mut decoded = format.storage().load(path)?;
+    decoded.classes.assign_ids();
+
+    match decoded.errors.first() {
+        Some(e) => Err(format!("{} invalid line(s), first at {}", decoded.errors.len(), e)),
+        None => Ok(decoded.classes)
+    }
+}
+
+/// Queries the assignments due in a range directly from storage, if the storage supports it.
+fn query_due(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Option<Vec<(String, Assignment)>> {
+    match storage().due_between(&config_file(), from, to)? {
+        Ok(mut l) => {
+            // Due times can name a period, so they're ordered here rather than in the query
+            l.sort_by_key(|(_, a)| a.due());
+            Some(l)
+        }
+        Err(e) => {
+            err(format!("Could not query assignments: {}", e));
+            None
+        }
+    }
+}
+
+/// Queries every assignment of active classes directly from storage, if the storage supports it.
+pub fn query_assignments() -> Option<Vec<(String, Assignment)>> {
+    query_due(None, None)
+}
+
+/// Queries the late assignments directly from storage, if the storage supports it.
+pub fn query_late() -> Option<Vec<(String, Assignment)>> {
+    let now = Local::now().naive_local();
+    let mut late = query_due(None, Some(now.date()))?;
+
+    late.retain(|(_, a)| a.is_late(now));
+
+    Some(late)
+}
+
+/// Moves the data to the file for another format, keeping the old file as a backup.
+/// Data files set with `--file` or `SKID_FILE` can't be moved, as the next run would still look for the old one.
+pub fn convert_config(classes: &Classes, format: Format) -> Result<PathBuf, String> {
+    if file_overridden() {
+        return Err(String::from("Cannot change the format of a data file set with '--file' or 'SKID_FILE'"));
+    }
+
+    let _lock = lock_config()?;
+
+    let old = config_file();
+    let new = format_file(format);
+
+    if old == new {
+        return Err(format!("Data is already stored in the {} format", format.name()));
     }
+
+    format.storage().save(&new, classes, None)
+        .map_err(|e| format!("Could not write '{}': {}", new.display(), e))?;
+
+    if old.exists() {
+        let backup = backup_path(&old);
+
+        rename(&old, &backup)
+            .map_err(|e| format!("Could not move '{}' to '{}': {}", old.display(), backup.display(), e))?;
+    }
+
+    set_loaded(classes);
+    Ok(new)
 }
 
-pub fn write_config(classes: &Classes) {
-    classes.write(config_file(), classes.encode());
+pub fn rejected_file() -> PathBuf {
+    let config = config_file();
+    let name = format!("{}.rejected", config.file_name().unwrap().to_string_lossy());
+    config.with_file_name(name)
+}
+
+/// Appends unparseable lines to the rejected file so they survive the next write.
+pub fn quarantine(errors: &[ParseError]) -> Result<PathBuf, String> {
+    let path = rejected_file();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+
+    for e in errors {
+        writeln!(file, "{}", e.source).map_err(|e| e.to_string())?;
+    }
+
+    Ok(path)
+}
+
+/// Returns `None` if the config is invalid and the user declined recovery.
+pub fn read_config() -> Option<Classes> {
+    let _lock = match lock_config() {
+        Ok(l) => l,
+        Err(e) => {
+            err(e);
+            return None;
+        }
+    };
+
+    let Decoded { mut classes, errors, version } = match storage().load(&config_file()) {
+        Ok(d) => d,
+        Err(e) => {
+            err(format!("Could not read '{}': {}", config_file().display(), e));
+            return None;
+        }
+    };
+
+    if !errors.is_empty() && !recover(&classes, &errors) {
+        return None;
+    }
+
+    classes.assign_ids();
+
+    if version < FORMAT_VERSION {
+        if let Err(e) = upgrade_config(&classes, version) {
+            err(e);
+            return None;
+        }
+    }
+
+    set_loaded(&classes);
+    Some(classes)
 }
 
-pub fn read_config() -> Classes {
-    match read_to_string(config_file()) {
-        Ok(s) => Classes::parse(s),
+/// Reports invalid lines and offers to quarantine them.
+/// Returns whether loading should continue.
+fn recover(classes: &Classes, errors: &[ParseError]) -> bool {
+    for e in errors {
+        err(format!("{}\n     {}", e, e.source));
+    }
+
+    let question = format!("\nFound {} invalid line(s) in '{}'.\nLoad the {} valid class(es) and move the invalid lines to '{}'?",
+        errors.len(),
+        config_file().display(),
+        classes.map.len(),
+        rejected_file().display()
+    );
+
+    if !confirm(&question) {
+        return false;
+    }
+
+    match quarantine(errors) {
+        Ok(path) => {
+            println!("Moved {} line(s) to '{}'", errors.len(), path.display());
+            true
+        }
         Err(e) => {
-            println!("Could not read config file: {}\nDefaulting to blank data", e);
-            Classes::new()
+            err(format!("Could not write rejected lines: {}", e));
+            false
         }
-    } 
-}
\ No newline at end of file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_backslashes_stay_literal() {
+        let (classes, errors) = Classes::parse_recover(&migrate(1, "hw,C:\\new\\tab,1,[read ch\\1;01-12-2026]"), 1);
+        let class = &classes.map["hw"];
+
+        assert!(errors.is_empty());
+        assert_eq!(class.name, "C:\\new\\tab");
+        assert_eq!(class.assignments[0].name, "read ch\\1");
+    }
+}
@@ -0,0 +1,204 @@
+extern crate chrono;
+
+use chrono::{Datelike, Duration, NaiveDate, TimeDelta, Weekday};
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun)
+];
+
+const MONTHS: [&str; 12] = [
+    "january", "february", "march", "april", "may", "june",
+    "july", "august", "september", "october", "november", "december"
+];
+
+/// Whether `s` is `name` cut down to at least three letters, like `tue` or `tues`.
+fn abbreviates(s: &str, name: &str) -> bool {
+    s.len() >= 3 && name.starts_with(s)
+}
+
+fn weekday(s: &str) -> Option<Weekday> {
+    WEEKDAYS.iter()
+        .find(|(n, _)| abbreviates(s, n))
+        .map(|(_, d)| *d)
+}
+
+/// The number of a month, starting from 1.
+fn month(s: &str) -> Option<u32> {
+    MONTHS.iter()
+        .position(|m| abbreviates(s, m))
+        .map(|i| i as u32 + 1)
+}
+
+/// The first given weekday after `today`.
+fn upcoming(day: Weekday, today: NaiveDate) -> NaiveDate {
+    let days = (7 + day.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+
+    today + Duration::days(if days == 0 { 7 } else { days as i64 })
+}
+
+/// An offset like `+3d`, `3d` or `2w`, or `None` if it's too large to represent.
+fn offset(s: &str) -> Option<TimeDelta> {
+    let s = s.strip_prefix('+').unwrap_or(s);
+
+    if let Some(n) = s.strip_suffix('d') {
+        n.parse().ok().and_then(TimeDelta::try_days)
+    } else if let Some(n) = s.strip_suffix('w') {
+        n.parse().ok().and_then(TimeDelta::try_weeks)
+    } else {
+        None
+    }
+}
+
+/// A month and day like `nov 3` or `3 nov`, with an optional year.
+/// Without a year, the next time that day comes around is used.
+fn month_day(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    let (m, d) = match (words.first()?, words.get(1)?) {
+        (a, b) if month(a).is_some() => (month(a)?, b.parse().ok()?),
+        (a, b) => (month(b)?, a.parse().ok()?)
+    };
+
+    match words {
+        [_, _, year] => NaiveDate::from_ymd_opt(year.parse().ok()?, m, d),
+        [_, _] => NaiveDate::from_ymd_opt(today.year(), m, d)
+            .filter(|date| *date >= today)
+            .or_else(|| NaiveDate::from_ymd_opt(today.year() + 1, m, d)),
+        _ => None
+    }
+}
+
+/// Resolves a date written relative to `today`, like `tomorrow`, `fri`,
+/// `next tue`, `+3d`, `2w`, `2026-11-03` or `nov 3`.
+pub fn resolve(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let lower = s.to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+
+    match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["next", "week"] => Some(today + Duration::weeks(1)),
+        ["this", day] => weekday(day).map(|d| upcoming(d, today)),
+        ["next", day] => weekday(day).map(|d| upcoming(d, today) + Duration::weeks(1)),
+        [word] => weekday(word)
+            .map(|d| upcoming(d, today))
+            .or_else(|| offset(word).and_then(|o| today.checked_add_signed(o)))
+            .or_else(|| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()),
+        _ => month_day(&words, today)
+    }
+}
+
+/// How many of the words at the start of `words` make up a date, longest first,
+/// leaving at least `keep` words for whatever follows.
+/// Dates in `format` are tried before the relative forms, since they can have spaces too.
+pub fn date_words(words: &[String], keep: usize, today: NaiveDate, format: &str) -> usize {
+    let max = words.len().saturating_sub(keep);
+
+    (1..=max)
+        .rev()
+        .find(|&n| NaiveDate::parse_from_str(&words[..n].join(" "), format).is_ok())
+        .or_else(|| (2..=max.min(3))
+            .rev()
+            .find(|&n| resolve(&words[..n].join(" "), today).is_some()))
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn words(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    /// A Sunday.
+    fn today() -> NaiveDate {
+        date(2026, 10, 18)
+    }
+
+    #[test]
+    fn relative_days() {
+        assert_eq!(resolve("today", today()), Some(today()));
+        assert_eq!(resolve("Tomorrow", today()), Some(date(2026, 10, 19)));
+        assert_eq!(resolve("yesterday", today()), Some(date(2026, 10, 17)));
+        assert_eq!(resolve("next week", today()), Some(date(2026, 10, 25)));
+    }
+
+    #[test]
+    fn weekdays_wrap_around() {
+        assert_eq!(resolve("mon", today()), Some(date(2026, 10, 19)));
+        assert_eq!(resolve("saturday", today()), Some(date(2026, 10, 24)));
+        assert_eq!(resolve("next tue", today()), Some(date(2026, 10, 27)));
+        assert_eq!(resolve("mo", today()), None);
+    }
+
+    #[test]
+    fn same_weekday_is_next_week() {
+        assert_eq!(resolve("sun", today()), Some(date(2026, 10, 25)));
+        assert_eq!(resolve("this sun", today()), Some(date(2026, 10, 25)));
+        assert_eq!(resolve("next sun", today()), Some(date(2026, 11, 1)));
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(resolve("+3d", today()), Some(date(2026, 10, 21)));
+        assert_eq!(resolve("2w", today()), Some(date(2026, 11, 1)));
+        assert_eq!(resolve("3x", today()), None);
+    }
+
+    #[test]
+    fn huge_offsets_overflow_to_none() {
+        assert_eq!(resolve("+99999999999999999d", today()), None);
+        assert_eq!(resolve("99999999999999w", today()), None);
+        assert_eq!(resolve("999999999d", today()), None);
+        assert_eq!(resolve("99999999999999999999d", today()), None);
+    }
+
+    #[test]
+    fn iso_dates() {
+        assert_eq!(resolve("2026-11-03", today()), Some(date(2026, 11, 3)));
+        assert_eq!(resolve("2026-02-30", today()), None);
+    }
+
+    #[test]
+    fn month_days_roll_over_the_year() {
+        assert_eq!(resolve("nov 3", today()), Some(date(2026, 11, 3)));
+        assert_eq!(resolve("3 November", today()), Some(date(2026, 11, 3)));
+        assert_eq!(resolve("oct 18", today()), Some(today()));
+        assert_eq!(resolve("oct 17", today()), Some(date(2027, 10, 17)));
+        assert_eq!(resolve("jan 5 2025", today()), Some(date(2025, 1, 5)));
+        assert_eq!(resolve("feb 30", today()), None);
+    }
+
+    #[test]
+    fn date_words_takes_the_longest_date() {
+        assert_eq!(date_words(&words("nov 3 2027 Essay"), 1, today(), "%d-%m-%Y"), 3);
+        assert_eq!(date_words(&words("nov 3 Essay"), 1, today(), "%d-%m-%Y"), 2);
+        assert_eq!(date_words(&words("next tue Lab report"), 1, today(), "%d-%m-%Y"), 2);
+        assert_eq!(date_words(&words("fri Essay"), 1, today(), "%d-%m-%Y"), 1);
+    }
+
+    #[test]
+    fn date_words_leaves_room_for_the_rest() {
+        assert_eq!(date_words(&words("nov 3"), 1, today(), "%d-%m-%Y"), 1);
+        assert_eq!(date_words(&words("nov 3 2027"), 1, today(), "%d-%m-%Y"), 2);
+        assert_eq!(date_words(&words("31-1-2027 Essay"), 1, today(), "%d-%m-%Y"), 1);
+    }
+
+    #[test]
+    fn date_words_takes_dates_in_the_input_format() {
+        assert_eq!(date_words(&words("3 11 2026 Essay"), 1, today(), "%d %m %Y"), 3);
+        assert_eq!(date_words(&words("3 11 2026"), 1, today(), "%d %m %Y"), 1);
+        assert_eq!(date_words(&words("nov 3 Essay"), 1, today(), "%d %m %Y"), 2);
+        assert_eq!(date_words(&words("3/11/2026 Essay draft"), 1, today(), "%d/%m/%Y"), 1);
+    }
+}
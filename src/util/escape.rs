@@ -0,0 +1,92 @@
+const SPECIAL: [char; 5] = ['\\', ',', ';', '[', ']'];
+
+pub fn escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            c if SPECIAL.contains(&c) => {
+                result.push('\\');
+                result.push(c);
+            }
+            c => result.push(c)
+        }
+    }
+
+    result
+}
+
+pub fn unescape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(e) => result.push(e),
+            None => result.push('\\')
+        }
+    }
+
+    result
+}
+
+/// Splits on every `sep` that isn't preceded by an escape.
+/// The returned slices are still escaped.
+pub fn split_unescaped(s: &str, sep: char) -> Vec<&str> {
+    let mut result: Vec<&str> = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == sep {
+            result.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+
+    result.push(&s[start..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AWKWARD: &str = "a,b;c[d]e\\f\ng\rh";
+
+    #[test]
+    fn escape_round_trips() {
+        assert_eq!(unescape(&escape(AWKWARD)), AWKWARD);
+        assert_eq!(unescape(&escape("")), "");
+    }
+
+    #[test]
+    fn escaped_text_has_no_bare_separators() {
+        let escaped = escape(AWKWARD);
+
+        assert_eq!(split_unescaped(&escaped, ',').len(), 1);
+        assert_eq!(split_unescaped(&escaped, ';').len(), 1);
+        assert!(!escaped.contains('\n'));
+    }
+
+    #[test]
+    fn split_keeps_escaped_separators() {
+        let joined = [escape("x,y"), escape("z\\"), escape("")].join(",");
+        let parts: Vec<String> = split_unescaped(&joined, ',').into_iter().map(unescape).collect();
+
+        assert_eq!(parts, ["x,y", "z\\", ""]);
+    }
+}
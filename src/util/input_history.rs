@@ -0,0 +1,80 @@
+use std::fs::read_to_string;
+
+use super::atomic::write_atomic;
+use super::config::history_file;
+use super::settings::settings;
+
+/// Keeps the last occurrence of each line, then the newest lines up to the size cap.
+fn compact(lines: &[String]) -> Vec<String> {
+    let mut kept: Vec<String> = Vec::new();
+
+    for line in lines.iter().rev() {
+        if !kept.contains(line) {
+            kept.push(line.clone());
+        }
+    }
+
+    kept.truncate(settings().history_size);
+    kept.reverse();
+
+    kept
+}
+
+/// Lines typed in interactive sessions, oldest first, kept in the history file.
+/// Duplicates are only dropped when the file is loaded and saved,
+/// so numbers stay the same for a whole session.
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    pub lines: Vec<String>
+}
+
+impl InputHistory {
+    pub fn load() -> Self {
+        let lines: Vec<String> = match read_to_string(history_file()) {
+            Ok(s) => s.lines().map(String::from).filter(|l| !l.trim().is_empty()).collect(),
+            Err(_) => Vec::new()
+        };
+
+        InputHistory { lines: compact(&lines) }
+    }
+
+    /// Adds a line and saves the history file.
+    pub fn add(&mut self, line: &str) -> Result<(), String> {
+        let line = line.trim();
+
+        if line.is_empty() || self.lines.last().map(String::as_str) == Some(line) {
+            return Ok(());
+        }
+
+        self.lines.push(String::from(line));
+        self.save()
+    }
+
+    pub fn pop(&mut self) -> Option<String> {
+        self.lines.pop()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let data: String = compact(&self.lines).into_iter()
+            .map(|l| format!("{}\n", l))
+            .collect();
+
+        write_atomic(&history_file(), &data)
+            .map_err(|e| format!("Could not write history file '{}': {}", history_file().display(), e))
+    }
+
+    /// Lines containing `query`, ignoring case, with their numbers starting from 1.
+    pub fn search(&self, query: &str) -> Vec<(usize, &String)> {
+        let query = query.to_lowercase();
+
+        self.lines.iter()
+            .enumerate()
+            .filter(|(_, l)| l.to_lowercase().contains(&query))
+            .map(|(i, l)| (i + 1, l))
+            .collect()
+    }
+
+    pub fn get(&self, number: usize) -> Option<&String> {
+        number.checked_sub(1).and_then(|i| self.lines.get(i))
+    }
+}
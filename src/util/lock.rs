@@ -0,0 +1,34 @@
+extern crate fs2;
+
+use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
+
+use fs2::FileExt;
+
+use super::config::config_file;
+
+/// Shared by every format of the same data file, so converting between formats stays locked.
+pub fn lock_file() -> PathBuf {
+    config_file().with_extension("lock")
+}
+
+/// Takes an exclusive advisory lock on the config, waiting for any other
+/// session to release it. The lock is released when the file is dropped.
+pub fn lock_config() -> Result<File, String> {
+    let path = lock_file();
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&path)
+        .map_err(|e| format!("Could not open lock file '{}': {}", path.display(), e))?;
+
+    if file.try_lock_exclusive().is_err() {
+        println!("Waiting for another skid session to release the config...");
+
+        file.lock_exclusive()
+            .map_err(|e| format!("Could not lock config: {}", e))?;
+    }
+
+    Ok(file)
+}
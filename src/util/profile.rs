@@ -0,0 +1,98 @@
+use std::fs::{create_dir_all, read_dir, read_to_string, remove_file};
+use std::path::PathBuf;
+
+use super::atomic::write_atomic;
+use super::config::base_dir;
+use super::storage::Format;
+use super::super::structs::classes::Classes;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub fn profiles_dir() -> PathBuf {
+    base_dir().join("skid-profiles")
+}
+
+fn active_file() -> PathBuf {
+    profiles_dir().join(".active")
+}
+
+pub fn valid_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_') {
+        Err(format!("Invalid profile name '{}'. Use lowercase letters, digits, '-' and '_'.", name))
+    } else {
+        Ok(())
+    }
+}
+
+/// The data path of a profile without a format extension.
+pub fn profile_stem(name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        base_dir().join("skid")
+    } else {
+        profiles_dir().join(name)
+    }
+}
+
+pub fn profile_exists(name: &str) -> bool {
+    name == DEFAULT_PROFILE || Format::all().into_iter().any(|f| f.path(&profile_stem(name)).exists())
+}
+
+pub fn active_profile() -> String {
+    match read_to_string(active_file()) {
+        Ok(s) if valid_profile_name(s.trim()).is_ok() && profile_exists(s.trim()) => String::from(s.trim()),
+        _ => String::from(DEFAULT_PROFILE)
+    }
+}
+
+pub fn set_active_profile(name: &str) -> Result<(), String> {
+    if name == DEFAULT_PROFILE {
+        let _ = remove_file(active_file());
+        return Ok(());
+    }
+
+    create_dir_all(profiles_dir()).map_err(|e| e.to_string())?;
+    write_atomic(&active_file(), name).map_err(|e| e.to_string())
+}
+
+/// Lists every profile, including the default one, by name.
+pub fn list_profiles() -> Vec<String> {
+    let extensions: Vec<&str> = Format::all().iter().filter_map(|f| f.extension()).collect();
+
+    let mut names: Vec<String> = match read_dir(profiles_dir()) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .filter(|p| match p.extension().and_then(|e| e.to_str()) {
+                Some(e) => extensions.contains(&e),
+                None => true
+            })
+            .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(String::from))
+            .filter(|n| valid_profile_name(n).is_ok())
+            .collect(),
+        Err(_) => Vec::new()
+    };
+
+    names.push(String::from(DEFAULT_PROFILE));
+    names.sort();
+    names.dedup();
+
+    names
+}
+
+/// Creates an empty data file for a new profile, returning its path.
+pub fn create_profile(name: &str, format: Format) -> Result<PathBuf, String> {
+    valid_profile_name(name)?;
+
+    if profile_exists(name) {
+        return Err(format!("Profile '{}' already exists", name));
+    }
+
+    create_dir_all(profiles_dir()).map_err(|e| e.to_string())?;
+
+    let path = format.path(&profile_stem(name));
+
+    format.storage()
+        .save(&path, &Classes::new(), None)
+        .map(|_| path)
+}
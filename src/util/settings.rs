@@ -0,0 +1,300 @@
+extern crate ansi_term;
+extern crate chrono;
+extern crate serde;
+
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use ansi_term::{Color, Style};
+use chrono::{Local, NaiveDate, NaiveTime, format::{Item, StrftimeItems}};
+use serde::{Deserialize, Serialize};
+
+/// User preferences, kept in a settings file separate from the data file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub input_date_format: String,
+    pub display_date_format: String,
+    pub display_time_format: String,
+    /// Start times of class periods, from period 1, like `8:00, 8:55`.
+    pub period_times: String,
+    pub default_sort: String,
+    pub color: bool,
+    pub history_size: usize,
+    pub label_color: String,
+    pub text_color: String,
+    pub number_color: String,
+    pub date_color: String,
+    pub class_color: String,
+    pub muted_color: String,
+    pub prompt_color: String,
+    pub error_color: String,
+    pub warning_color: String
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            input_date_format: String::from("%d-%m-%Y"),
+            display_date_format: String::from("%b %e %Y"),
+            display_time_format: String::from("%-I:%M %p"),
+            period_times: String::new(),
+            default_sort: String::from("period"),
+            color: true,
+            history_size: 1000,
+            label_color: String::from("blue"),
+            text_color: String::from("green"),
+            number_color: String::from("yellow"),
+            date_color: String::from("green"),
+            class_color: String::from("purple"),
+            muted_color: String::from("black"),
+            prompt_color: String::from("blue"),
+            error_color: String::from("red"),
+            warning_color: String::from("yellow")
+        }
+    }
+}
+
+/// Setting keys and their descriptions, in display order.
+pub const KEYS: [(&str, &str); 16] = [
+    ("input_date_format", "Format of dates you type, in strftime syntax"),
+    ("display_date_format", "Format of displayed dates, in strftime syntax"),
+    ("display_time_format", "Format of displayed due times, in strftime syntax"),
+    ("period_times", "Start times of class periods in order, like 8:00, 8:55"),
+    ("default_sort", "Class order in lists: id, name or period"),
+    ("color", "Whether to use colors: true or false"),
+    ("history_size", "How many typed commands to remember between sessions"),
+    ("label_color", "Color of property labels"),
+    ("text_color", "Color of names and text values"),
+    ("number_color", "Color of numbers and indexes"),
+    ("date_color", "Color of due dates"),
+    ("class_color", "Color of class tags"),
+    ("muted_color", "Color of list punctuation"),
+    ("prompt_color", "Color of prompts and echoed commands"),
+    ("error_color", "Color of error tags and unknown commands"),
+    ("warning_color", "Color of warning tags")
+];
+
+const COLORS: [(&str, Color); 8] = [
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("purple", Color::Purple),
+    ("cyan", Color::Cyan),
+    ("white", Color::White)
+];
+
+fn parse_color(s: &str) -> Result<Color, String> {
+    let name = s.trim().to_lowercase();
+
+    match COLORS.iter().find(|(n, _)| *n == name) {
+        Some((_, c)) => Ok(*c),
+        None => match name.parse::<u8>() {
+            Ok(n) => Ok(Color::Fixed(n)),
+            Err(_) => Err(format!("Invalid color '{}'. Use a color name or a number from 0 to 255.", s))
+        }
+    }
+}
+
+fn check_date_format(format: &str, parse: bool) -> Result<(), String> {
+    if format.trim().is_empty() || StrftimeItems::new(format).any(|i| i == Item::Error) {
+        return Err(format!("Invalid date format '{}'", format));
+    }
+
+    // Formats with time specifiers can't display a date
+    let today = Local::now().date_naive();
+    let mut formatted = String::new();
+
+    if write!(formatted, "{}", today.format(format)).is_err() {
+        return Err(format!("Date format '{}' can only contain date specifiers", format));
+    }
+
+    if parse && NaiveDate::parse_from_str(&formatted, format) != Ok(today) {
+        return Err(format!("Date format '{}' must include a day, month and year", format));
+    }
+
+    Ok(())
+}
+
+fn check_time_format(format: &str) -> Result<(), String> {
+    if format.trim().is_empty() || StrftimeItems::new(format).any(|i| i == Item::Error) {
+        return Err(format!("Invalid time format '{}'", format));
+    }
+
+    let mut formatted = String::new();
+
+    if write!(formatted, "{}", NaiveTime::MIN.format(format)).is_err() {
+        return Err(format!("Time format '{}' can only contain time specifiers", format));
+    }
+
+    Ok(())
+}
+
+fn parse_period_times(s: &str) -> Result<Vec<NaiveTime>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| NaiveTime::parse_from_str(t, "%H:%M").map_err(|_| format!("Invalid period time '{}'. Use 24-hour times like 13:05.", t)))
+        .collect()
+}
+
+impl Settings {
+    /// When a period starts, if it's in the `period_times` setting.
+    pub fn period_time(&self, period: usize) -> Option<NaiveTime> {
+        parse_period_times(&self.period_times).ok()?
+            .get(period.checked_sub(1)?)
+            .copied()
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "input_date_format" => self.input_date_format.clone(),
+            "display_date_format" => self.display_date_format.clone(),
+            "display_time_format" => self.display_time_format.clone(),
+            "period_times" => self.period_times.clone(),
+            "default_sort" => self.default_sort.clone(),
+            "color" => self.color.to_string(),
+            "history_size" => self.history_size.to_string(),
+            "label_color" => self.label_color.clone(),
+            "text_color" => self.text_color.clone(),
+            "number_color" => self.number_color.clone(),
+            "date_color" => self.date_color.clone(),
+            "class_color" => self.class_color.clone(),
+            "muted_color" => self.muted_color.clone(),
+            "prompt_color" => self.prompt_color.clone(),
+            "error_color" => self.error_color.clone(),
+            "warning_color" => self.warning_color.clone(),
+            _ => return None
+        };
+
+        Some(value)
+    }
+
+    pub fn set(&mut self, key: &str, value: String) -> Result<(), String> {
+        match key {
+            "input_date_format" => {
+                check_date_format(&value, true)?;
+                self.input_date_format = value;
+            }
+            "display_date_format" => {
+                check_date_format(&value, false)?;
+                self.display_date_format = value;
+            }
+            "display_time_format" => {
+                check_time_format(&value)?;
+                self.display_time_format = value;
+            }
+            "period_times" => {
+                parse_period_times(&value)?;
+                self.period_times = value.trim().to_string();
+            }
+            "default_sort" => {
+                let sort = value.to_lowercase();
+
+                if !["id", "name", "period"].contains(&sort.as_str()) {
+                    return Err(format!("Invalid sorting method '{}'", value));
+                }
+
+                self.default_sort = sort;
+            }
+            "color" => {
+                self.color = value.to_lowercase().parse::<bool>()
+                    .map_err(|_| format!("Invalid value '{}'. Use true or false.", value))?;
+            }
+            "history_size" => {
+                self.history_size = value.trim().parse::<usize>()
+                    .map_err(|_| format!("Invalid value '{}'. Use a number of commands.", value))?;
+            }
+            "label_color" | "text_color" | "number_color" | "date_color" | "class_color" | "muted_color"
+                | "prompt_color" | "error_color" | "warning_color" => {
+                parse_color(&value)?;
+
+                let name = value.trim().to_lowercase();
+
+                match key {
+                    "label_color" => self.label_color = name,
+                    "text_color" => self.text_color = name,
+                    "number_color" => self.number_color = name,
+                    "date_color" => self.date_color = name,
+                    "class_color" => self.class_color = name,
+                    "muted_color" => self.muted_color = name,
+                    "prompt_color" => self.prompt_color = name,
+                    "error_color" => self.error_color = name,
+                    _ => self.warning_color = name
+                }
+            }
+            _ => return Err(format!("Invalid setting '{}'", key))
+        }
+
+        Ok(())
+    }
+
+    /// Checks every value, for settings read from a file.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut copy = Settings::default();
+
+        for (key, _) in KEYS.iter() {
+            copy.set(key, self.get(key).unwrap())
+                .map_err(|e| format!("{} (setting '{}')", e, key))?;
+        }
+
+        Ok(())
+    }
+}
+
+static SETTINGS: Mutex<Option<Settings>> = Mutex::new(None);
+
+/// Runs `f` with the current settings, or the defaults if none were loaded, without copying them.
+/// `f` must not read the settings again.
+fn with_settings<T>(f: impl FnOnce(&Settings) -> T) -> T {
+    f(SETTINGS.lock().unwrap().get_or_insert_with(Settings::default))
+}
+
+/// The current settings, or the defaults if none were loaded.
+pub fn settings() -> Settings {
+    with_settings(Settings::clone)
+}
+
+pub fn set_settings(settings: Settings) {
+    *SETTINGS.lock().unwrap() = Some(settings);
+}
+
+/// What a piece of displayed text is, which decides its style.
+#[derive(Debug, Clone, Copy)]
+pub enum Role {
+    Label,
+    Text,
+    Number,
+    Date,
+    Class,
+    Muted,
+    Prompt,
+    Error,
+    Warning
+}
+
+pub fn style(role: Role) -> Style {
+    with_settings(|s| {
+        if !s.color {
+            return Style::new();
+        }
+
+        let (color, bold) = match role {
+            Role::Label => (&s.label_color, false),
+            Role::Text => (&s.text_color, true),
+            Role::Number => (&s.number_color, true),
+            Role::Date => (&s.date_color, false),
+            Role::Class => (&s.class_color, true),
+            Role::Muted => (&s.muted_color, true),
+            Role::Prompt => (&s.prompt_color, true),
+            Role::Error => (&s.error_color, false),
+            Role::Warning => (&s.warning_color, false)
+        };
+
+        let style = parse_color(color).unwrap_or(Color::White).normal();
+
+        if bold { style.bold() } else { style }
+    })
+}
@@ -0,0 +1,236 @@
+extern crate chrono;
+extern crate rusqlite;
+
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+
+use super::config::FORMAT_VERSION;
+use super::storage::{Decoded, Storage};
+use super::super::structs::{classes::Classes, class::Class, assignment::{Assignment, DueTime}, completion::Completion};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS classes (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        period INTEGER NOT NULL,
+        archived_at TEXT
+    );
+    CREATE TABLE IF NOT EXISTS assignments (
+        class_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        due_date TEXT NOT NULL,
+        due_time TEXT,
+        id TEXT,
+        PRIMARY KEY (class_id, position)
+    );
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS assignments_due_date ON assignments (due_date);
+    CREATE TABLE IF NOT EXISTS completed (
+        class_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        due_date TEXT,
+        due_time TEXT,
+        id TEXT,
+        completed_at TEXT,
+        late INTEGER NOT NULL DEFAULT 0,
+        archived INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (class_id, position)
+    );
+";
+
+/// Tables, columns and their types added after the first version, in the order they were added.
+const ADDED_COLUMNS: [(&str, &str, &str); 9] = [
+    ("assignments", "due_time", "TEXT"),
+    ("assignments", "id", "TEXT"),
+    ("completed", "due_date", "TEXT"),
+    ("completed", "due_time", "TEXT"),
+    ("completed", "id", "TEXT"),
+    ("completed", "completed_at", "TEXT"),
+    ("completed", "late", "INTEGER NOT NULL DEFAULT 0"),
+    ("classes", "archived_at", "TEXT"),
+    ("completed", "archived", "INTEGER NOT NULL DEFAULT 0")
+];
+
+/// Stores classes in an SQLite database, updating only the classes that changed.
+pub struct SqliteStorage;
+
+fn open(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    conn.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+
+    // Databases from older format versions are missing columns added since
+    for (table, column, kind) in ADDED_COLUMNS {
+        if conn.prepare(&format!("SELECT {} FROM {}", column, table)).is_err() {
+            conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, kind)).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(conn)
+}
+
+fn delete_class(tx: &Transaction, id: &str) -> rusqlite::Result<()> {
+    tx.execute("DELETE FROM classes WHERE id = ?1", params![id])?;
+    tx.execute("DELETE FROM assignments WHERE class_id = ?1", params![id])?;
+    tx.execute("DELETE FROM completed WHERE class_id = ?1", params![id])?;
+
+    Ok(())
+}
+
+fn insert_class(tx: &Transaction, c: &Class) -> rusqlite::Result<()> {
+    tx.execute("INSERT INTO classes (id, name, period, archived_at) VALUES (?1, ?2, ?3, ?4)", params![c.id, c.name, c.period as i64, c.archived_at])?;
+
+    for (i, a) in c.assignments.iter().enumerate() {
+        tx.execute("INSERT INTO assignments (class_id, position, name, due_date, due_time, id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![c.id, i as i64, a.name, a.due_date, a.due_time.map(|t| t.encode()), a.id])?;
+    }
+
+    // Archived completions follow the completed ones, marked as archived
+    let completions = c.completed.iter().map(|d| (d, false)).chain(c.archive.iter().map(|d| (d, true)));
+
+    for (i, (d, archived)) in completions.enumerate() {
+        tx.execute("INSERT INTO completed (class_id, position, name, due_date, due_time, id, completed_at, late, archived) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![c.id, i as i64, d.name, d.due_date, d.due_time.map(|t| t.encode()), d.id, d.completed_at, d.late, archived])?;
+    }
+
+    Ok(())
+}
+
+/// Reads an assignment from a row of `name, due_date, due_time, id` starting at column `start`.
+fn assignment(r: &Row, start: usize) -> rusqlite::Result<Assignment> {
+    let due_time: Option<String> = r.get(start + 2)?;
+    let id: Option<String> = r.get(start + 3)?;
+
+    Ok(Assignment::new(id.unwrap_or_default(), r.get(start)?, r.get(start + 1)?, due_time.as_deref().and_then(DueTime::parse)))
+}
+
+impl SqliteStorage {
+    fn read(conn: &Connection) -> rusqlite::Result<Classes> {
+        let mut classes = Classes::new();
+
+        let next_id = conn.query_row("SELECT value FROM meta WHERE key = 'next_id'", [], |r| r.get::<_, i64>(0)).optional()?;
+        classes.next_id = next_id.map_or(1, |n| n.max(1) as usize);
+
+        let mut stmt = conn.prepare("SELECT id, name, period, archived_at FROM classes")?;
+        let rows = stmt.query_map([], |r| {
+            let mut c = Class::new(r.get(0)?, r.get(1)?, r.get::<_, i64>(2)? as usize);
+            c.archived_at = r.get(3)?;
+
+            Ok(c)
+        })?;
+
+        for c in rows {
+            let c = c?;
+            classes.add_class(c.id.clone(), c);
+        }
+
+        let mut stmt = conn.prepare("SELECT class_id, name, due_date, due_time, id FROM assignments ORDER BY class_id, position")?;
+        let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, assignment(r, 1)?)))?;
+
+        for row in rows {
+            let (id, a) = row?;
+
+            if let Some(c) = classes.map.get_mut(&id) {
+                c.assignments.push(a);
+            }
+        }
+
+        let mut stmt = conn.prepare("SELECT class_id, name, due_date, due_time, id, completed_at, late, archived FROM completed ORDER BY class_id, position")?;
+        let rows = stmt.query_map([], |r| {
+            // Rows from older versions only have names, and the rest is left empty
+            let due_time: Option<String> = r.get(3)?;
+            let id: Option<String> = r.get(4)?;
+
+            let completion = Completion {
+                id: id.unwrap_or_default(),
+                name: r.get(1)?,
+                due_date: r.get::<_, Option<NaiveDate>>(2)?,
+                due_time: due_time.as_deref().and_then(DueTime::parse),
+                completed_at: r.get::<_, Option<NaiveDateTime>>(5)?,
+                late: r.get(6)?
+            };
+
+            Ok((r.get::<_, String>(0)?, completion, r.get::<_, bool>(7)?))
+        })?;
+
+        for row in rows {
+            let (id, completion, archived) = row?;
+
+            if let Some(c) = classes.map.get_mut(&id) {
+                if archived {
+                    c.archive.push(completion);
+                } else {
+                    c.completed.push(completion);
+                }
+            }
+        }
+
+        Ok(classes)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self, path: &Path) -> Result<Decoded, String> {
+        let conn = open(path)?;
+
+        let version = match conn.query_row("PRAGMA user_version", [], |r| r.get::<_, i64>(0)) {
+            Ok(0) => FORMAT_VERSION,
+            Ok(v) => v as usize,
+            Err(e) => return Err(e.to_string())
+        };
+
+        if version > FORMAT_VERSION {
+            return Err(format!("Format version {} is newer than this version of skid supports ({})", version, FORMAT_VERSION));
+        }
+
+        let classes = Self::read(&conn).map_err(|e| e.to_string())?;
+
+        Ok(Decoded { classes, errors: Vec::new(), version })
+    }
+
+    fn save(&self, path: &Path, classes: &Classes, previous: Option<&Classes>) -> Result<(), String> {
+        let mut conn = open(path)?;
+
+        let result = conn.transaction().and_then(|tx| {
+            let changed = match previous {
+                Some(p) => p.changed_ids(classes),
+                None => {
+                    tx.execute_batch("DELETE FROM classes; DELETE FROM assignments; DELETE FROM completed;")?;
+                    classes.map.keys().cloned().collect()
+                }
+            };
+
+            for id in changed {
+                delete_class(&tx, &id)?;
+
+                if let Some(c) = classes.map.get(&id) {
+                    insert_class(&tx, c)?;
+                }
+            }
+
+            tx.execute("INSERT OR REPLACE INTO meta (key, value) VALUES ('next_id', ?1)", params![classes.next_id as i64])?;
+            tx.pragma_update(None, "user_version", FORMAT_VERSION as i64)?;
+            tx.commit()
+        });
+
+        result.map_err(|e| e.to_string())
+    }
+
+    fn due_between(&self, path: &Path, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Option<Result<Vec<(String, Assignment)>, String>> {
+        let query = |conn: Connection| -> rusqlite::Result<Vec<(String, Assignment)>> {
+            let mut stmt = conn.prepare("SELECT a.class_id, a.name, a.due_date, a.due_time, a.id FROM assignments a JOIN classes c ON c.id = a.class_id \
+                WHERE c.archived_at IS NULL AND (?1 IS NULL OR a.due_date >= ?1) AND (?2 IS NULL OR a.due_date <= ?2) ORDER BY a.due_date, a.class_id, a.position")?;
+            let rows = stmt.query_map(params![from, to], |r| Ok((r.get(0)?, assignment(r, 1)?)))?;
+
+            rows.collect()
+        };
+
+        Some(open(path).and_then(|conn| query(conn).map_err(|e| e.to_string())))
+    }
+}
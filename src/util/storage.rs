@@ -0,0 +1,316 @@
+extern crate chrono;
+extern crate serde;
+extern crate serde_json;
+extern crate toml;
+
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::atomic::write_atomic;
+use super::config::{FORMAT_VERSION, migrate, split_next_id, split_version, with_header};
+use super::super::structs::{classes::{Classes, SortingMethod}, class::Class, assignment::Assignment, error::ParseError};
+
+#[cfg(feature = "sqlite")]
+use super::sqlite::SqliteStorage;
+
+/// The result of decoding a config file.
+pub struct Decoded {
+    pub classes: Classes,
+    /// Items that couldn't be read. Only formats that can recover from bad items produce these.
+    pub errors: Vec<ParseError>,
+    /// The format version the file was written in, before any migrations.
+    pub version: usize
+}
+
+pub trait Storage {
+    /// Loads a whole data file. Errors that prevent reading it at all are returned as `Err`.
+    fn load(&self, path: &Path) -> Result<Decoded, String>;
+
+    /// Saves all classes. `previous` is what the file held before, if known,
+    /// so that storages able to update in place only need to write changed classes.
+    fn save(&self, path: &Path, classes: &Classes, previous: Option<&Classes>) -> Result<(), String>;
+
+    /// Assignments due between two dates (inclusive) paired with their class IDs, ordered by due date.
+    /// A `None` bound leaves that end of the range open.
+    /// Returns `None` if the storage can't query this without loading every class.
+    fn due_between(&self, _path: &Path, _from: Option<NaiveDate>, _to: Option<NaiveDate>) -> Option<Result<Vec<(String, Assignment)>, String>> {
+        None
+    }
+}
+
+/// A storage that reads and writes the whole file as text.
+pub trait TextStorage {
+    fn encode(&self, classes: &Classes) -> Result<String, String>;
+
+    /// Decodes a whole file. Errors that prevent reading the file at all are returned as `Err`.
+    fn decode(&self, s: &str) -> Result<Decoded, String>;
+}
+
+impl<T: TextStorage> Storage for T {
+    fn load(&self, path: &Path) -> Result<Decoded, String> {
+        let s = read_to_string(path).map_err(|e| e.to_string())?;
+        self.decode(&s)
+    }
+
+    fn save(&self, path: &Path, classes: &Classes, _previous: Option<&Classes>) -> Result<(), String> {
+        write_atomic(path, &self.encode(classes)?).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Line,
+    Json,
+    Toml,
+    #[cfg(feature = "sqlite")]
+    Sqlite
+}
+
+impl Format {
+    pub fn all() -> Vec<Format> {
+        vec![
+            Format::Line,
+            Format::Json,
+            Format::Toml,
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Line => "line",
+            Format::Json => "json",
+            Format::Toml => "toml",
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => "sqlite"
+        }
+    }
+
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Format::Line => None,
+            Format::Json => Some("json"),
+            Format::Toml => Some("toml"),
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => Some("db")
+        }
+    }
+
+    /// The data file path for this format, given a path without an extension.
+    pub fn path(&self, stem: &Path) -> PathBuf {
+        match self.extension() {
+            Some(e) => stem.with_extension(e),
+            None => stem.to_path_buf()
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        Self::all().into_iter().find(|f| f.name() == s.to_lowercase())
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+
+        Self::all().into_iter()
+            .find(|f| f.extension().map(String::from) == extension)
+            .unwrap_or(Format::Line)
+    }
+
+    pub fn storage(&self) -> Box<dyn Storage> {
+        match self {
+            Format::Line => Box::new(LineStorage),
+            Format::Json => Box::new(JsonStorage),
+            Format::Toml => Box::new(TomlStorage),
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => Box::new(SqliteStorage)
+        }
+    }
+
+    /// The storage used to display encoded data. Binary formats fall back to the line format.
+    pub fn text_storage(&self) -> Box<dyn TextStorage> {
+        match self {
+            Format::Json => Box::new(JsonStorage),
+            Format::Toml => Box::new(TomlStorage),
+            _ => Box::new(LineStorage)
+        }
+    }
+}
+
+/// The native line-based format, one encoded class per line.
+pub struct LineStorage;
+
+impl TextStorage for LineStorage {
+    fn encode(&self, classes: &Classes) -> Result<String, String> {
+        Ok(with_header(&classes.encode(), classes.next_id))
+    }
+
+    fn decode(&self, s: &str) -> Result<Decoded, String> {
+        let (version, body) = split_version(s)?;
+        let (next_id, body) = split_next_id(body)?;
+        let (mut classes, mut errors) = Classes::parse_recover(&migrate(version, body), version);
+
+        classes.next_id = next_id.unwrap_or(classes.next_id);
+
+        // Account for the header lines
+        let offset = (version > 1) as usize + next_id.is_some() as usize;
+
+        for e in errors.iter_mut() {
+            e.line += offset;
+        }
+
+        Ok(Decoded { classes, errors, version })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Document {
+    version: usize,
+    #[serde(default)]
+    next_id: usize,
+    classes: Vec<Class>
+}
+
+impl Document {
+    fn new(classes: &Classes) -> Self {
+        Document {
+            version: FORMAT_VERSION,
+            next_id: classes.next_id,
+            classes: classes.sorted_all(SortingMethod::Period).into_iter().cloned().collect()
+        }
+    }
+
+    fn decoded(self) -> Result<Decoded, String> {
+        if self.version > FORMAT_VERSION {
+            return Err(format!("Format version {} is newer than this version of skid supports ({})", self.version, FORMAT_VERSION));
+        }
+
+        let mut classes = Classes::new();
+        classes.next_id = self.next_id.max(1);
+
+        for c in self.classes {
+            if classes.map.contains_key(&c.id) {
+                return Err(format!("Duplicate class ID '{}'", c.id));
+            }
+
+            classes.add_class(c.id.clone(), c);
+        }
+
+        Ok(Decoded { classes, errors: Vec::new(), version: self.version })
+    }
+}
+
+pub struct JsonStorage;
+
+impl TextStorage for JsonStorage {
+    fn encode(&self, classes: &Classes) -> Result<String, String> {
+        serde_json::to_string_pretty(&Document::new(classes)).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, s: &str) -> Result<Decoded, String> {
+        if s.trim().is_empty() {
+            return Document::new(&Classes::new()).decoded();
+        }
+
+        serde_json::from_str::<Document>(s)
+            .map_err(|e| e.to_string())?
+            .decoded()
+    }
+}
+
+pub struct TomlStorage;
+
+impl TextStorage for TomlStorage {
+    fn encode(&self, classes: &Classes) -> Result<String, String> {
+        toml::to_string_pretty(&Document::new(classes)).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, s: &str) -> Result<Decoded, String> {
+        if s.trim().is_empty() {
+            return Document::new(&Classes::new()).decoded();
+        }
+
+        toml::from_str::<Document>(s)
+            .map_err(|e| e.to_string())?
+            .decoded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::structs::assignment::DueTime;
+    use chrono::NaiveTime;
+
+    fn classes() -> Classes {
+        let mut classes = Classes::new();
+        let date = NaiveDate::from_ymd_opt(2026, 11, 3).unwrap();
+
+        classes.add_class_data(String::from("math"), String::from("Math; \"Algebra\" \\ 2"), 1);
+        classes.add_class_data(String::from("art"), String::from("Art"), 2);
+
+        for (name, time) in [
+            ("Essay", None),
+            ("Lab [report]", Some(DueTime::At(NaiveTime::from_hms_opt(23, 59, 0).unwrap()))),
+            ("Quiz", Some(DueTime::Period(3))),
+            ("Reading", None)
+        ] {
+            let id = classes.next_assignment_id();
+            classes.map.get_mut("math").unwrap().add_assignment(id, String::from(name), date, time);
+        }
+
+        let math = classes.map.get_mut("math").unwrap();
+        math.complete_assignment(4).unwrap();
+        math.clean();
+        math.complete_assignment(1).unwrap();
+
+        let id = classes.next_assignment_id();
+        classes.map.get_mut("art").unwrap().add_assignment(id, String::from("Sketch"), date, None);
+        classes.archive_class("art").unwrap();
+
+        classes
+    }
+
+    fn round_trip(storage: &dyn TextStorage) {
+        let classes = classes();
+        let decoded = storage.decode(&storage.encode(&classes).unwrap()).unwrap();
+
+        assert!(decoded.errors.is_empty());
+        assert_eq!(decoded.version, FORMAT_VERSION);
+        assert_eq!(decoded.classes.encode(), classes.encode());
+        assert_eq!(decoded.classes.next_id, classes.next_id);
+        assert!(decoded.classes.changed_ids(&classes).is_empty());
+
+        let math = &decoded.classes.map["math"];
+        assert_eq!(math.assignments.len(), 2);
+        assert_eq!(math.assignments[1].due_time, Some(DueTime::Period(3)));
+        assert_eq!(math.completed.len(), 1);
+        assert_eq!(math.archive.len(), 1);
+        assert!(decoded.classes.map["art"].is_archived());
+    }
+
+    #[test]
+    fn json_round_trips() {
+        round_trip(&JsonStorage);
+    }
+
+    #[test]
+    fn toml_round_trips() {
+        round_trip(&TomlStorage);
+    }
+
+    #[test]
+    fn line_format_round_trips() {
+        round_trip(&LineStorage);
+    }
+
+    #[test]
+    fn empty_files_decode_to_no_classes() {
+        for storage in [&JsonStorage as &dyn TextStorage, &TomlStorage] {
+            assert!(storage.decode("  \n").unwrap().classes.map.is_empty());
+        }
+    }
+}